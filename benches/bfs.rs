@@ -1,5 +1,5 @@
 use divan::Bencher;
-use eurorust_2025_workshop::bfs::{bfs_naive, generate_graph};
+use eurorust_2025_workshop::bfs::{CsrGraph, bfs_naive, generate_graph};
 
 fn main() {
     divan::main();
@@ -58,3 +58,19 @@ fn bfs_large_graph(bencher: Bencher) {
         assert_eq!(result[2500], 5949, "Node at position 2500 should be 5949");
     });
 }
+
+#[divan::bench]
+fn bfs_csr_large_graph(bencher: Bencher) {
+    let graph = generate_graph(10000);
+    let csr = CsrGraph::from(&graph);
+    let expected = bfs_naive(&graph, 0);
+
+    bencher.bench_local(|| {
+        let result = divan::black_box(divan::black_box(&csr).bfs_csr(divan::black_box(0)));
+
+        assert_eq!(
+            result, expected,
+            "CSR BFS should match the adjacency-list BFS"
+        );
+    });
+}
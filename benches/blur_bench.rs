@@ -0,0 +1,28 @@
+use eurorust_2025_workshop::blur::{box_blur, gaussian_blur};
+use image::RgbImage;
+
+fn main() {
+    divan::main();
+}
+
+fn load_test_image() -> RgbImage {
+    image::open("data/large.jpg")
+        .expect("Failed to load test image")
+        .to_rgb8()
+}
+
+/// A single box blur pass over the rows and the columns
+#[divan::bench(sample_count = 3, sample_size = 5)]
+fn bench_box_blur(bencher: divan::Bencher) {
+    let img = load_test_image();
+
+    bencher.bench(|| box_blur(divan::black_box(&img), divan::black_box(4)));
+}
+
+/// Three box blurs with the radii for sigma 3.0
+#[divan::bench(sample_count = 3, sample_size = 5)]
+fn bench_gaussian_blur(bencher: divan::Bencher) {
+    let img = load_test_image();
+
+    bencher.bench(|| gaussian_blur(divan::black_box(&img), divan::black_box(3.0)));
+}
@@ -0,0 +1,19 @@
+use eurorust_2025_workshop::color_filters::*;
+use image::RgbImage;
+
+fn main() {
+    divan::main();
+}
+
+fn load_test_image() -> RgbImage {
+    image::open("data/large.jpg")
+        .expect("Failed to load test image")
+        .to_rgb8()
+}
+
+#[divan::bench(sample_count = 3, sample_size = 5)]
+fn bench_adjust_saturation(bencher: divan::Bencher) {
+    let img = load_test_image();
+
+    bencher.bench(|| adjust_saturation(divan::black_box(&img), divan::black_box(1.5)));
+}
@@ -1,4 +1,5 @@
 use eurorust_2025_workshop::dna_matcher::*;
+use eurorust_2025_workshop::nucleotide::{count_bases, count_bases_simd};
 
 fn main() {
     divan::main();
@@ -22,3 +23,116 @@ fn dna_matcher() {
         matches.len()
     );
 }
+
+#[divan::bench(sample_count = 2, sample_size = 3)]
+fn memchr_matcher(bencher: divan::Bencher) {
+    let genome = std::fs::read("genome.fasta").expect(
+        "Failed to read genome.fasta\n\n Make sure to run 'cargo run --release --bin generate_fasta'",
+    );
+    let pattern = b"AGTCCGTA";
+
+    bencher.bench_local(|| {
+        let matches = divan::black_box(memchr_search_bytes(
+            divan::black_box(&genome),
+            divan::black_box(pattern),
+        ));
+
+        assert_eq!(matches.len(), 4927, "Expected 4927 matching lines");
+    });
+}
+
+/// The genome only contains A/C/G/T, so a pattern with an N is ruled out by a single memchr
+#[divan::bench(sample_count = 2, sample_size = 3)]
+fn memchr_matcher_absent(bencher: divan::Bencher) {
+    let genome = std::fs::read("genome.fasta").expect(
+        "Failed to read genome.fasta\n\n Make sure to run 'cargo run --release --bin generate_fasta'",
+    );
+    let pattern = b"AGTCNGTA";
+
+    bencher.bench_local(|| {
+        let matches = divan::black_box(memchr_search_bytes(
+            divan::black_box(&genome),
+            divan::black_box(pattern),
+        ));
+
+        assert!(matches.is_empty(), "Expected no matching lines");
+    });
+}
+
+/// Same search as `memchr_matcher`, borrowing the lines instead of copying each one
+#[divan::bench(sample_count = 2, sample_size = 3)]
+fn memchr_matcher_refs(bencher: divan::Bencher) {
+    let genome = std::fs::read("genome.fasta").expect(
+        "Failed to read genome.fasta\n\n Make sure to run 'cargo run --release --bin generate_fasta'",
+    );
+    let pattern = b"AGTCCGTA";
+
+    bencher.bench_local(|| {
+        let matches = divan::black_box(memchr_search_refs(
+            divan::black_box(&genome),
+            divan::black_box(pattern),
+        ));
+
+        assert_eq!(matches.len(), 4927, "Expected 4927 matching lines");
+    });
+}
+
+/// Same search as `memchr_matcher`, deduplicating lines with FxHash instead of SipHash
+#[divan::bench(sample_count = 2, sample_size = 3)]
+fn memchr_matcher_fxhash(bencher: divan::Bencher) {
+    let genome = std::fs::read("genome.fasta").expect(
+        "Failed to read genome.fasta\n\n Make sure to run 'cargo run --release --bin generate_fasta'",
+    );
+    let pattern = b"AGTCCGTA";
+
+    bencher.bench_local(|| {
+        let matches = divan::black_box(memchr_search_bytes_fast(
+            divan::black_box(&genome),
+            divan::black_box(pattern),
+        ));
+
+        assert_eq!(matches.len(), 4927, "Expected 4927 matching lines");
+    });
+}
+
+#[divan::bench(sample_count = 2, sample_size = 3)]
+fn rabin_karp_matcher(bencher: divan::Bencher) {
+    let genome = std::fs::read("genome.fasta").expect(
+        "Failed to read genome.fasta\n\n Make sure to run 'cargo run --release --bin generate_fasta'",
+    );
+    let pattern = b"AGTCCGTA";
+
+    bencher.bench_local(|| {
+        let offsets = divan::black_box(rabin_karp_search(
+            divan::black_box(&genome),
+            divan::black_box(pattern),
+        ));
+
+        // 4927 lines, three of which contain the pattern twice
+        assert_eq!(offsets.len(), 4930, "Expected 4930 occurrences");
+    });
+}
+
+#[divan::bench(sample_count = 2, sample_size = 3)]
+fn base_count(bencher: divan::Bencher) {
+    let genome = std::fs::read("genome.fasta").expect(
+        "Failed to read genome.fasta\n\n Make sure to run 'cargo run --release --bin generate_fasta'",
+    );
+
+    bencher
+        .counter(divan::counter::BytesCount::new(genome.len()))
+        .bench_local(|| count_bases(divan::black_box(&genome)));
+}
+
+/// Same count as `base_count`, with one vector of lane counters per base
+#[divan::bench(sample_count = 2, sample_size = 3)]
+fn base_count_simd(bencher: divan::Bencher) {
+    let genome = std::fs::read("genome.fasta").expect(
+        "Failed to read genome.fasta\n\n Make sure to run 'cargo run --release --bin generate_fasta'",
+    );
+    assert_eq!(count_bases_simd(&genome), count_bases(&genome));
+
+    bencher
+        .counter(divan::counter::BytesCount::new(genome.len()))
+        .bench_local(|| count_bases_simd(divan::black_box(&genome)));
+}
@@ -44,3 +44,20 @@ fn bench_brightness_contrast_gamma(bencher: divan::Bencher) {
         )
     });
 }
+
+/// Brightness/contrast and gamma fused into one table, applied in a single pass
+#[divan::bench(sample_count = 2, sample_size = 3)]
+fn bench_combined_lut(bencher: divan::Bencher) {
+    let img = load_test_image();
+    let lut = combined_lut(30, 0.3, 2.2);
+
+    bencher.bench(|| apply_combined_lut(divan::black_box(&img), divan::black_box(&lut)));
+}
+
+#[divan::bench(sample_count = 2, sample_size = 3)]
+fn bench_combined_lut_parallel(bencher: divan::Bencher) {
+    let img = load_test_image();
+    let lut = combined_lut(30, 0.3, 2.2);
+
+    bencher.bench(|| apply_combined_lut_parallel(divan::black_box(&img), divan::black_box(&lut)));
+}
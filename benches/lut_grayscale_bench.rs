@@ -1,3 +1,4 @@
+use eurorust_2025_workshop::helpers::gradient_image;
 use eurorust_2025_workshop::lut_grayscale::*;
 use image::{RgbImage};
 
@@ -33,3 +34,44 @@ fn bench_rgb_to_gray_big_lut(bencher: divan::Bencher) {
 
     bencher.bench(|| rgb_to_gray_big_lut(divan::black_box(&img), divan::black_box(&lut)));
 }
+
+#[divan::bench(sample_count = 3, sample_size = 5)]
+fn bench_rgb_to_gray_big_lut_parallel(bencher: divan::Bencher) {
+    let img = load_test_image();
+    let lut = GrayscaleLutBig::new();
+
+    bencher.bench(|| rgb_to_gray_big_lut_parallel(divan::black_box(&img), divan::black_box(&lut)));
+}
+
+/// Side lengths of the square synthetic images, generated in memory so
+/// these benches need no fixture
+const SYNTHETIC_SIZES: [u32; 3] = [256, 1024, 4096];
+
+#[divan::bench(args = SYNTHETIC_SIZES, sample_count = 3, sample_size = 5)]
+fn bench_rgb_to_gray_naive_synthetic(bencher: divan::Bencher, size: u32) {
+    let img = gradient_image(size, size);
+
+    bencher
+        .counter(divan::counter::ItemsCount::new(img.pixels().len()))
+        .bench(|| rgb_to_gray_naive(divan::black_box(&img)));
+}
+
+#[divan::bench(args = SYNTHETIC_SIZES, sample_count = 3, sample_size = 5)]
+fn bench_rgb_to_gray_small_lut_synthetic(bencher: divan::Bencher, size: u32) {
+    let img = gradient_image(size, size);
+    let lut = GrayscaleLut::new();
+
+    bencher
+        .counter(divan::counter::ItemsCount::new(img.pixels().len()))
+        .bench(|| rgb_to_gray_small_lut(divan::black_box(&img), divan::black_box(&lut)));
+}
+
+#[divan::bench(args = SYNTHETIC_SIZES, sample_count = 3, sample_size = 5)]
+fn bench_rgb_to_gray_big_lut_synthetic(bencher: divan::Bencher, size: u32) {
+    let img = gradient_image(size, size);
+    let lut = GrayscaleLutBig::new();
+
+    bencher
+        .counter(divan::counter::ItemsCount::new(img.pixels().len()))
+        .bench(|| rgb_to_gray_big_lut(divan::black_box(&img), divan::black_box(&lut)));
+}
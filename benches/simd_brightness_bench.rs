@@ -1,5 +1,6 @@
 use eurorust_2025_workshop::simd_brightness::{
-    brightness_autovec, brightness_scalar, brightness_simd,
+    brightness_autovec, brightness_scalar, brightness_simd, brightness_simd_generic,
+    linear_transform_simd,
 };
 use image::RgbImage;
 
@@ -33,3 +34,45 @@ fn bench_brightness_simd(bencher: divan::Bencher) {
 
     bencher.bench(|| brightness_simd(divan::black_box(&img), divan::black_box(30)));
 }
+
+/// Hand-written AVX2 intrinsics, to compare with the portable_simd versions
+#[cfg(target_arch = "x86_64")]
+#[divan::bench(sample_count = 3, sample_size = 5)]
+fn bench_brightness_avx2(bencher: divan::Bencher) {
+    use eurorust_2025_workshop::simd_brightness::brightness_avx2;
+
+    let img = load_test_image();
+
+    bencher.bench(|| brightness_avx2(divan::black_box(&img), divan::black_box(30)));
+}
+
+/// Scale and brightness fused in a single pass
+#[divan::bench(sample_count = 3, sample_size = 5)]
+fn bench_linear_transform_simd(bencher: divan::Bencher) {
+    let img = load_test_image();
+
+    bencher.bench(|| {
+        linear_transform_simd(
+            divan::black_box(&img),
+            divan::black_box(1.2),
+            divan::black_box(-20),
+        )
+    });
+}
+
+/// SSE (16) vs AVX2 (32) vs AVX-512 (64) sized vectors, reported per pixel
+#[divan::bench(consts = [16, 32, 64], sample_count = 3, sample_size = 5)]
+fn bench_brightness_simd_lanes<const LANES: usize>(bencher: divan::Bencher) {
+    let img = load_test_image();
+    let pixels = img.width() as usize * img.height() as usize;
+
+    // Check the output once, outside of the measured loop
+    assert!(
+        brightness_simd_generic::<LANES>(&img, 30) == brightness_autovec(&img, 30),
+        "SIMD output differs from the reference"
+    );
+
+    bencher
+        .counter(divan::counter::ItemsCount::new(pixels))
+        .bench(|| brightness_simd_generic::<LANES>(divan::black_box(&img), divan::black_box(30)));
+}
@@ -0,0 +1,57 @@
+use eurorust_2025_workshop::lut_grayscale::{GrayscaleLut, rgb_to_gray_small_lut};
+use eurorust_2025_workshop::simd_grayscale::*;
+use image::RgbImage;
+
+fn main() {
+    divan::main();
+}
+
+fn load_test_image() -> RgbImage {
+    image::open("data/large.jpg")
+        .expect("Failed to load test image")
+        .to_rgb8()
+}
+
+#[divan::bench(sample_count = 3, sample_size = 5)]
+fn bench_rgb_to_gray_small_lut(bencher: divan::Bencher) {
+    let img = load_test_image();
+    let lut = GrayscaleLut::new();
+
+    bencher.bench(|| rgb_to_gray_small_lut(divan::black_box(&img), divan::black_box(&lut)));
+}
+
+#[divan::bench(sample_count = 3, sample_size = 5)]
+fn bench_rgb_to_gray_simd_swizzle(bencher: divan::Bencher) {
+    let img = load_test_image();
+
+    bencher.bench(|| rgb_to_gray_simd_swizzle(divan::black_box(&img)));
+}
+
+/// Same SIMD conversion into a buffer allocated once, outside the timed loop
+#[divan::bench(sample_count = 3, sample_size = 5)]
+fn bench_rgb_to_gray_simd_into(bencher: divan::Bencher) {
+    let img = load_test_image();
+    let mut output = vec![0u8; img.as_raw().len() / 3];
+
+    bencher.bench_local(|| {
+        rgb_to_gray_simd_into(
+            divan::black_box(img.as_raw()),
+            divan::black_box(&mut output),
+        )
+    });
+}
+
+/// Binarization of the grayscale image, scalar LUT vs SIMD select
+#[divan::bench(sample_count = 3, sample_size = 5)]
+fn bench_threshold(bencher: divan::Bencher) {
+    let gray = rgb_to_gray_small_lut(&load_test_image(), &GrayscaleLut::new());
+
+    bencher.bench(|| threshold(divan::black_box(&gray), divan::black_box(128)));
+}
+
+#[divan::bench(sample_count = 3, sample_size = 5)]
+fn bench_threshold_simd(bencher: divan::Bencher) {
+    let gray = rgb_to_gray_small_lut(&load_test_image(), &GrayscaleLut::new());
+
+    bencher.bench(|| threshold_simd(divan::black_box(&gray), divan::black_box(128)));
+}
@@ -0,0 +1,26 @@
+use eurorust_2025_workshop::transform::*;
+use image::RgbImage;
+
+fn main() {
+    divan::main();
+}
+
+fn load_test_image() -> RgbImage {
+    image::open("data/large.jpg")
+        .expect("Failed to load test image")
+        .to_rgb8()
+}
+
+#[divan::bench(sample_count = 3, sample_size = 5)]
+fn bench_flip_horizontal(bencher: divan::Bencher) {
+    let img = load_test_image();
+
+    bencher.bench(|| flip_horizontal(divan::black_box(&img)));
+}
+
+#[divan::bench(sample_count = 3, sample_size = 5)]
+fn bench_flip_vertical(bencher: divan::Bencher) {
+    let img = load_test_image();
+
+    bencher.bench(|| flip_vertical(divan::black_box(&img)));
+}
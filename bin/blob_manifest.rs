@@ -0,0 +1,140 @@
+use eurorust_2025_workshop::blob_corruption_checker::{CrcManifest, chunk_crcs};
+use memmap2::Mmap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+/// Amount of data hashed between two progress updates
+const PROGRESS_BLOCK_SIZE: usize = 64 * 1024 * 1024;
+
+fn main() {
+    let args = parse_args(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        eprintln!("Usage: blob_manifest --input <path> [--chunk-size <bytes>] [--output <path>]");
+        std::process::exit(1);
+    });
+
+    let file = File::open(&args.input).expect("Failed to open input");
+    let data = unsafe { Mmap::map(&file).expect("Failed to map input") };
+
+    println!(
+        "Hashing {} ({} bytes) in {} byte chunks...",
+        args.input,
+        data.len(),
+        args.chunk_size
+    );
+
+    // Hash one block at a time (in parallel within each block) to report progress
+    let block_size = PROGRESS_BLOCK_SIZE.div_ceil(args.chunk_size) * args.chunk_size;
+    let mut crcs = Vec::with_capacity(data.len().div_ceil(args.chunk_size));
+    let mut hashed = 0;
+
+    for block in data.chunks(block_size) {
+        crcs.extend(chunk_crcs(block, args.chunk_size));
+        hashed += block.len();
+
+        print!("\r{:>3}% hashed", hashed * 100 / data.len());
+        std::io::stdout().flush().unwrap();
+    }
+    println!();
+
+    let manifest = CrcManifest {
+        chunk_size: args.chunk_size,
+        length: data.len() as u64,
+        crcs,
+    };
+
+    let output = File::create(&args.output).expect("Failed to create manifest");
+    manifest
+        .write_to(BufWriter::new(output))
+        .expect("Failed to write manifest");
+
+    println!(
+        "Done! Wrote {} checksums to {}",
+        manifest.crcs.len(),
+        args.output
+    );
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Args {
+    input: String,
+    chunk_size: usize,
+    /// Defaults to the input path with a `.manifest` suffix
+    output: String,
+}
+
+/// Parse `--input <path>`, `--chunk-size <bytes>` (1 KB by default) and `--output <path>`
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut input = None;
+    let mut chunk_size = 1024;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => input = Some(args.next().ok_or("--input requires a path")?),
+            "--output" => output = Some(args.next().ok_or("--output requires a path")?),
+            "--chunk-size" => {
+                let value = args.next().ok_or("--chunk-size requires a value")?;
+                chunk_size = value
+                    .parse()
+                    .map_err(|_| format!("Invalid chunk size: {}", value))?;
+                if chunk_size == 0 {
+                    return Err("The chunk size must be at least 1 byte".to_string());
+                }
+            }
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    let input: String = input.ok_or("--input is required")?;
+    let output = output.unwrap_or_else(|| format!("{}.manifest", input));
+
+    Ok(Args {
+        input,
+        chunk_size,
+        output,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> impl Iterator<Item = String> {
+        values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args(args(&["--input", "reference.bin"])),
+            Ok(Args {
+                input: "reference.bin".to_string(),
+                chunk_size: 1024,
+                output: "reference.bin.manifest".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_args(args(&[
+                "--chunk-size",
+                "4096",
+                "--input",
+                "a.bin",
+                "--output",
+                "a.crc"
+            ])),
+            Ok(Args {
+                input: "a.bin".to_string(),
+                chunk_size: 4096,
+                output: "a.crc".to_string(),
+            })
+        );
+        assert!(parse_args(args(&[])).is_err());
+        assert!(parse_args(args(&["--input", "a.bin", "--chunk-size", "0"])).is_err());
+        assert!(parse_args(args(&["--input", "a.bin", "--verbose"])).is_err());
+    }
+}
@@ -5,9 +5,19 @@ use std::io::Write;
 fn main() {
     const SIZE_MB: usize = 500; // File size in MB
 
-    println!("Generating blob test files ({} MB)...", SIZE_MB);
+    // Corrupted bytes are XORed with this mask, `--mask 0x01` flips a single bit
+    let mask = parse_mask(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        eprintln!("Usage: generate_blobs [--mask <byte>]");
+        std::process::exit(1);
+    });
 
-    generate_blob("reference.bin", SIZE_MB, &[]).expect("Failed to generate reference.bin");
+    println!(
+        "Generating blob test files ({} MB, corruption mask {:#04x})...",
+        SIZE_MB, mask
+    );
+
+    generate_blob("reference.bin", SIZE_MB, &[], mask).expect("Failed to generate reference.bin");
 
     // Generate random corruptions that scale with file size
     // 1 corruption per 10MB
@@ -21,17 +31,42 @@ fn main() {
         corruption_points.push((offset, length));
     }
 
-    generate_blob("corrupted.bin", SIZE_MB, &corruption_points)
+    generate_blob("corrupted.bin", SIZE_MB, &corruption_points, mask)
         .expect("Failed to generate corrupted.bin");
 
     println!("Done! Generated reference.bin and corrupted.bin");
 }
 
+/// Parse the optional `--mask <byte>` argument, accepting decimal or `0x` hex
+fn parse_mask(mut args: impl Iterator<Item = String>) -> Result<u8, String> {
+    let mut mask = 0xFF;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mask" => {
+                let value = args.next().ok_or("--mask requires a value")?;
+                let parsed = match value.strip_prefix("0x") {
+                    Some(hex) => u8::from_str_radix(hex, 16),
+                    None => value.parse(),
+                };
+                mask = parsed.map_err(|_| format!("Invalid mask byte: {}", value))?;
+                if mask == 0 {
+                    return Err("A mask of 0 would not corrupt anything".to_string());
+                }
+            }
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    Ok(mask)
+}
+
 /// Generate a blob file with the given size and optional corruption points
 fn generate_blob(
     path: &str,
     size_mb: usize,
     corruption_points: &[(u64, u64)], // (offset, length) pairs to corrupt
+    mask: u8,
 ) -> std::io::Result<()> {
     let mut file = File::create(path)?;
     let size_bytes = size_mb * 1024 * 1024;
@@ -52,24 +87,12 @@ fn generate_blob(
         }
 
         // Apply corruptions if any fall in this chunk
-        for &(corrupt_offset, corrupt_length) in corruption_points {
-            let chunk_start = written as u64;
-            let chunk_end = chunk_start + to_write as u64;
-
-            if corrupt_offset < chunk_end && corrupt_offset + corrupt_length > chunk_start {
-                // This corruption overlaps with current chunk
-                let local_start = corrupt_offset.saturating_sub(chunk_start) as usize;
-                let local_end = std::cmp::min(
-                    (corrupt_offset + corrupt_length - chunk_start) as usize,
-                    to_write,
-                );
-
-                // Corrupt by XOR with 0xFF
-                for byte in &mut buffer[local_start..local_end] {
-                    *byte ^= 0xFF;
-                }
-            }
-        }
+        apply_corruptions(
+            &mut buffer[..to_write],
+            written as u64,
+            corruption_points,
+            mask,
+        );
 
         file.write_all(&buffer[..to_write])?;
         written += to_write;
@@ -78,3 +101,68 @@ fn generate_blob(
 
     Ok(())
 }
+
+/// XOR every byte of `buffer` covered by a corruption point with `mask`
+///
+/// `buffer_start` is the file offset of `buffer[0]`, corruption points are
+/// `(offset, length)` pairs in file offsets and may only partially overlap.
+fn apply_corruptions(
+    buffer: &mut [u8],
+    buffer_start: u64,
+    corruption_points: &[(u64, u64)],
+    mask: u8,
+) {
+    let buffer_end = buffer_start + buffer.len() as u64;
+
+    for &(corrupt_offset, corrupt_length) in corruption_points {
+        if corrupt_offset < buffer_end && corrupt_offset + corrupt_length > buffer_start {
+            // This corruption overlaps with current chunk
+            let local_start = corrupt_offset.saturating_sub(buffer_start) as usize;
+            let local_end = std::cmp::min(
+                (corrupt_offset + corrupt_length - buffer_start) as usize,
+                buffer.len(),
+            );
+
+            for byte in &mut buffer[local_start..local_end] {
+                *byte ^= mask;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_corruptions_single_bit() {
+        let mut buffer = vec![0b1010_1010u8; 8];
+
+        // Starts before the buffer and ends inside it
+        apply_corruptions(&mut buffer, 100, &[(98, 4)], 0x01);
+
+        assert_eq!(buffer[0], 0b1010_1011);
+        assert_eq!(buffer[1], 0b1010_1011);
+        assert!(buffer[2..].iter().all(|&byte| byte == 0b1010_1010));
+    }
+
+    #[test]
+    fn test_apply_corruptions_outside_buffer() {
+        let mut buffer = vec![0u8; 8];
+
+        apply_corruptions(&mut buffer, 100, &[(0, 100), (108, 10)], 0xFF);
+
+        assert!(buffer.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn test_parse_mask() {
+        let args = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(parse_mask(args(&[]).into_iter()), Ok(0xFF));
+        assert_eq!(parse_mask(args(&["--mask", "0x01"]).into_iter()), Ok(0x01));
+        assert_eq!(parse_mask(args(&["--mask", "16"]).into_iter()), Ok(16));
+        assert!(parse_mask(args(&["--mask", "0"]).into_iter()).is_err());
+        assert!(parse_mask(args(&["--mask"]).into_iter()).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+/// Batch processing: apply a filter to every image of a directory in parallel
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use image::RgbImage;
+use rayon::prelude::*;
+
+/// Apply `op` to every `.jpg`/`.png` file of `input_dir` and save the results
+/// under the same file names in `output_dir`
+///
+/// Images are processed in parallel with rayon. Other files are skipped.
+/// Returns the number of images processed.
+pub fn process_directory(
+    input_dir: &str,
+    output_dir: &str,
+    op: impl Fn(&RgbImage) -> RgbImage + Sync,
+) -> io::Result<usize> {
+    fs::create_dir_all(output_dir)?;
+
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(input_dir)? {
+        let path = entry?.path();
+        if path.is_file() && is_supported_image(&path) {
+            paths.push(path);
+        }
+    }
+
+    paths.par_iter().try_for_each(|path| {
+        let img = image::open(path).map_err(io::Error::other)?.to_rgb8();
+        let output = op(&img);
+
+        let file_name = path.file_name().expect("read_dir entries have a file name");
+        output
+            .save(Path::new(output_dir).join(file_name))
+            .map_err(io::Error::other)
+    })?;
+
+    Ok(paths.len())
+}
+
+fn is_supported_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("jpg") || ext.eq_ignore_ascii_case("png"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transform::flip_horizontal;
+    use image::{ImageBuffer, Rgb};
+
+    #[test]
+    fn test_process_directory() {
+        let root = std::env::temp_dir().join("eurorust_process_directory");
+        let input_dir = root.join("input");
+        let output_dir = root.join("output");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&input_dir).unwrap();
+
+        let img: RgbImage = ImageBuffer::from_fn(4, 2, |x, y| Rgb([x as u8 * 60, y as u8 * 60, 0]));
+        img.save(input_dir.join("first.png")).unwrap();
+        img.save(input_dir.join("second.png")).unwrap();
+        fs::write(input_dir.join("notes.txt"), "not an image").unwrap();
+
+        let processed = process_directory(
+            input_dir.to_str().unwrap(),
+            output_dir.to_str().unwrap(),
+            flip_horizontal,
+        )
+        .unwrap();
+
+        assert_eq!(processed, 2);
+        let first = image::open(output_dir.join("first.png")).unwrap().to_rgb8();
+        assert_eq!(first, flip_horizontal(&img));
+        assert!(output_dir.join("second.png").exists());
+        assert!(!output_dir.join("notes.txt").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,7 +1,9 @@
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
 
 /// A simple graph represented as an adjacency list
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Graph {
     /// adjacency[i] contains a list of nodes adjacent to node i
     pub adjacency: Vec<Vec<usize>>,
@@ -21,6 +23,166 @@ impl Graph {
     pub fn num_nodes(&self) -> usize {
         self.adjacency.len()
     }
+
+    /// Check whether there is an edge from `from` to `to`
+    pub fn has_edge(&self, from: usize, to: usize) -> bool {
+        self.adjacency
+            .get(from)
+            .is_some_and(|neighbors| neighbors.contains(&to))
+    }
+
+    /// Iterate over the neighbors of `node`, empty if the node doesn't exist
+    pub fn neighbors(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        self.adjacency.get(node).into_iter().flatten().copied()
+    }
+
+    /// The same graph with every edge flipped, so neighbors become in-neighbors
+    pub fn reversed(&self) -> Graph {
+        Graph {
+            adjacency: self.build_in_adjacency(),
+        }
+    }
+
+    /// Incoming-edge lists: `in_adjacency[i]` holds every node with an edge to `i`
+    ///
+    /// Only the lists are built, for algorithms that need the transpose next
+    /// to the graph itself (e.g. [`strongly_connected_components`]).
+    pub fn build_in_adjacency(&self) -> Vec<Vec<usize>> {
+        let mut in_adjacency = vec![vec![]; self.num_nodes()];
+        for (from, neighbors) in self.adjacency.iter().enumerate() {
+            for &to in neighbors {
+                in_adjacency[to].push(from);
+            }
+        }
+        in_adjacency
+    }
+
+    /// Sort each adjacency list and remove duplicate edges
+    ///
+    /// `generate_graph` picks random targets, so the same edge can be added twice.
+    pub fn dedup_edges(&mut self) {
+        for neighbors in &mut self.adjacency {
+            neighbors.sort_unstable();
+            neighbors.dedup();
+        }
+    }
+
+    /// Histogram of out-degrees: index `d` is the number of nodes with `d` outgoing edges
+    ///
+    /// Duplicate edges are counted, call [`Graph::dedup_edges`] first to ignore
+    /// them. The histogram stops at the highest degree, so it is empty for a
+    /// graph without nodes.
+    pub fn degree_distribution(&self) -> Vec<usize> {
+        degree_histogram(self.adjacency.iter().map(Vec::len))
+    }
+
+    /// Histogram of in-degrees: index `d` is the number of nodes with `d` incoming edges
+    pub fn in_degree_distribution(&self) -> Vec<usize> {
+        let mut in_degrees = vec![0; self.num_nodes()];
+        for neighbor in self.adjacency.iter().flatten() {
+            in_degrees[*neighbor] += 1;
+        }
+
+        degree_histogram(in_degrees)
+    }
+
+    /// Save the graph in a compact binary format
+    ///
+    /// Layout: the node count, then for each node its edge count followed by
+    /// its neighbors. Every number is written as an LEB128 varint, so small
+    /// node IDs only take a byte or two.
+    pub fn save_binary(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        write_varint(&mut writer, self.num_nodes())?;
+        for neighbors in &self.adjacency {
+            write_varint(&mut writer, neighbors.len())?;
+            for &neighbor in neighbors {
+                write_varint(&mut writer, neighbor)?;
+            }
+        }
+
+        writer.flush()
+    }
+
+    /// Load a graph previously written by [`Graph::save_binary`]
+    ///
+    /// Every node takes at least one byte (its edge count) and every edge at
+    /// least one more, so counts larger than the rest of the file are rejected
+    /// before anything is allocated for them, as are edges to missing nodes.
+    pub fn load_binary(path: &str) -> io::Result<Self> {
+        Self::from_binary(&std::fs::read(path)?)
+    }
+
+    /// Parse the content of a file written by [`Graph::save_binary`]
+    fn from_binary(mut reader: &[u8]) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let nodes = read_varint(&mut reader)?;
+        if nodes > reader.len() {
+            return Err(invalid(format!(
+                "{nodes} nodes in {} bytes of edge lists",
+                reader.len()
+            )));
+        }
+
+        let mut graph = Graph::new(nodes);
+        for neighbors in &mut graph.adjacency {
+            let edges = read_varint(&mut reader)?;
+            if edges > reader.len() {
+                return Err(invalid(format!(
+                    "{edges} edges in {} remaining bytes",
+                    reader.len()
+                )));
+            }
+
+            neighbors.reserve_exact(edges);
+            for _ in 0..edges {
+                let neighbor = read_varint(&mut reader)?;
+                if neighbor >= nodes {
+                    return Err(invalid(format!(
+                        "edge to node {neighbor} in a graph of {nodes} nodes"
+                    )));
+                }
+                neighbors.push(neighbor);
+            }
+        }
+
+        Ok(graph)
+    }
+}
+
+fn write_varint(writer: &mut impl Write, mut value: usize) -> io::Result<()> {
+    while value >= 0x80 {
+        writer.write_all(&[(value as u8) | 0x80])?;
+        value >>= 7;
+    }
+    writer.write_all(&[value as u8])
+}
+
+fn read_varint(reader: &mut impl Read) -> io::Result<usize> {
+    let mut value = 0usize;
+    let mut shift = 0;
+
+    loop {
+        let mut byte = [0u8];
+        reader.read_exact(&mut byte)?;
+
+        // The last byte may only use the bits left in a usize
+        let payload = (byte[0] & 0x7F) as usize;
+        if shift >= usize::BITS || (payload << shift) >> shift != payload {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "varint is too long",
+            ));
+        }
+        value |= payload << shift;
+
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
 }
 
 /// Naive BFS implementation using Vec as a queue (intentionally slow)
@@ -38,16 +200,311 @@ pub fn bfs_naive(graph: &Graph, start: usize) -> Vec<usize> {
         let node = queue.remove(0);
         result.push(node);
 
-        if let Some(neighbors) = graph.adjacency.get(node) {
-            for &neighbor in neighbors {
+        for neighbor in graph.neighbors(node) {
+            if visited.insert(neighbor) {
+                queue.push(neighbor);
+            }
+        }
+    }
+
+    result
+}
+
+/// BFS ignoring edge directions: both out-edges and in-edges are followed
+///
+/// `generate_graph` builds directed edges, so a plain BFS misses nodes that
+/// only link *to* the visited ones. The in-edges come from [`Graph::reversed`],
+/// built once before the traversal. Out-neighbors are queued before
+/// in-neighbors. Empty if `start` isn't a node.
+pub fn bfs_undirected(graph: &Graph, start: usize) -> Vec<usize> {
+    if start >= graph.num_nodes() {
+        return Vec::new();
+    }
+
+    let reversed = graph.reversed();
+
+    let mut visited = vec![false; graph.num_nodes()];
+    let mut queue = VecDeque::new();
+    let mut result = Vec::new();
+
+    queue.push_back(start);
+    visited[start] = true;
+
+    while let Some(node) = queue.pop_front() {
+        result.push(node);
+
+        for neighbor in graph.neighbors(node).chain(reversed.neighbors(node)) {
+            if !visited[neighbor] {
+                visited[neighbor] = true;
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    result
+}
+
+/// Strongly connected components, with Kosaraju's algorithm
+///
+/// A first DFS over the out-edges records the order in which nodes finish,
+/// then a second pass over the in-edges, from the last finished node, collects
+/// one component per traversal. Both passes use an explicit stack, so deep
+/// graphs can't overflow the call stack. Each component is sorted, and
+/// components are listed in the order the second pass finds them.
+pub fn strongly_connected_components(graph: &Graph) -> Vec<Vec<usize>> {
+    let nodes = graph.num_nodes();
+
+    // First pass: post-order of a DFS over the out-edges
+    let mut finish_order = Vec::with_capacity(nodes);
+    let mut visited = vec![false; nodes];
+    // (node, index of the next neighbor to visit)
+    let mut stack = Vec::new();
+
+    for root in 0..nodes {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        stack.push((root, 0));
+
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            match graph.adjacency[node].get(*next) {
+                Some(&neighbor) => {
+                    *next += 1;
+                    if !visited[neighbor] {
+                        visited[neighbor] = true;
+                        stack.push((neighbor, 0));
+                    }
+                }
+                None => {
+                    finish_order.push(node);
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    // Second pass: whatever is reachable over the in-edges is one component
+    let in_adjacency = graph.build_in_adjacency();
+    let mut assigned = vec![false; nodes];
+    let mut components = Vec::new();
+
+    for &root in finish_order.iter().rev() {
+        if assigned[root] {
+            continue;
+        }
+        assigned[root] = true;
+
+        let mut component = Vec::new();
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            component.push(node);
+            for &neighbor in &in_adjacency[node] {
+                if !assigned[neighbor] {
+                    assigned[neighbor] = true;
+                    stack.push(neighbor);
+                }
+            }
+        }
+
+        component.sort_unstable();
+        components.push(component);
+    }
+
+    components
+}
+
+/// The same graph in Compressed Sparse Row (CSR) layout
+///
+/// All neighbor lists are stored back to back in a single `edges` vector, and
+/// the neighbors of node `i` are `edges[offsets[i]..offsets[i + 1]]`.
+/// Walking neighbors is then a linear scan over contiguous memory instead of
+/// chasing one heap allocation per node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsrGraph {
+    pub edges: Vec<usize>,
+    /// `offsets.len() == num_nodes + 1`
+    pub offsets: Vec<usize>,
+}
+
+impl From<&Graph> for CsrGraph {
+    fn from(graph: &Graph) -> Self {
+        let mut offsets = Vec::with_capacity(graph.num_nodes() + 1);
+        let mut edges = Vec::with_capacity(graph.adjacency.iter().map(Vec::len).sum());
+
+        offsets.push(0);
+        for neighbors in &graph.adjacency {
+            edges.extend_from_slice(neighbors);
+            offsets.push(edges.len());
+        }
+
+        CsrGraph { edges, offsets }
+    }
+}
+
+impl CsrGraph {
+    pub fn num_nodes(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// BFS over the flat CSR arrays, with a `Vec<bool>` visited set and a `VecDeque` queue
+    /// Returns the order in which nodes were visited, empty if `start` isn't a node
+    pub fn bfs_csr(&self, start: usize) -> Vec<usize> {
+        if start >= self.num_nodes() {
+            return Vec::new();
+        }
+
+        let mut visited = vec![false; self.num_nodes()];
+        let mut queue = VecDeque::new();
+        let mut result = Vec::with_capacity(self.num_nodes());
+
+        queue.push_back(start);
+        visited[start] = true;
+
+        while let Some(node) = queue.pop_front() {
+            result.push(node);
+
+            for &neighbor in &self.edges[self.offsets[node]..self.offsets[node + 1]] {
+                if !visited[neighbor] {
+                    visited[neighbor] = true;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Same as [`CsrGraph::bfs_csr`], with a visited set reused across calls
+    ///
+    /// `visited` is reset at the start of the call in O(1), so running a BFS
+    /// from every node doesn't pay O(V) per run just to clear the set. Empty if
+    /// `start` isn't a node.
+    pub fn bfs_with_generations(
+        &self,
+        start: usize,
+        visited: &mut GenerationVisited,
+    ) -> Vec<usize> {
+        assert!(
+            visited.len() >= self.num_nodes(),
+            "Visited set is smaller than the graph"
+        );
+        if start >= self.num_nodes() {
+            return Vec::new();
+        }
+        visited.reset();
+
+        let mut queue = VecDeque::new();
+        let mut result = Vec::with_capacity(self.num_nodes());
+
+        queue.push_back(start);
+        visited.insert(start);
+
+        while let Some(node) = queue.pop_front() {
+            result.push(node);
+
+            for &neighbor in &self.edges[self.offsets[node]..self.offsets[node + 1]] {
                 if visited.insert(neighbor) {
-                    queue.push(neighbor);
+                    queue.push_back(neighbor);
                 }
             }
         }
+
+        result
     }
+}
 
-    result
+/// A visited set that can be cleared in O(1)
+///
+/// Each node stores the generation in which it was last visited, and a node
+/// counts as visited only if that matches the current generation. Clearing the
+/// set just starts a new generation. The stamps are only rewritten when the
+/// `u32` counter wraps around, once every 4 billion resets.
+#[derive(Debug, Clone)]
+pub struct GenerationVisited {
+    stamps: Vec<u32>,
+    generation: u32,
+}
+
+impl GenerationVisited {
+    pub fn new(nodes: usize) -> Self {
+        GenerationVisited {
+            stamps: vec![0; nodes],
+            generation: 1,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.stamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stamps.is_empty()
+    }
+
+    /// Forget every visited node
+    pub fn reset(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        if self.generation == 0 {
+            // Stamps from 4 billion generations ago would look current again
+            self.stamps.fill(0);
+            self.generation = 1;
+        }
+    }
+
+    /// Mark `node` as visited, returning whether it wasn't visited yet
+    pub fn insert(&mut self, node: usize) -> bool {
+        let newly_visited = self.stamps[node] != self.generation;
+        self.stamps[node] = self.generation;
+        newly_visited
+    }
+
+    pub fn contains(&self, node: usize) -> bool {
+        self.stamps[node] == self.generation
+    }
+}
+
+/// Seeded random walk of at most `steps` steps, each following a random out-edge
+///
+/// The returned path starts with `start`, so it holds at most `steps + 1`
+/// nodes. The walk stops early on a node without out-edges. Duplicate edges
+/// make their target proportionally more likely. Empty if `start` isn't a node.
+pub fn random_walk(graph: &Graph, start: usize, steps: usize, seed: u64) -> Vec<usize> {
+    use rand::{Rng, SeedableRng};
+    if start >= graph.num_nodes() {
+        return Vec::new();
+    }
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+
+    let mut path = vec![start];
+    let mut current = start;
+
+    for _ in 0..steps {
+        let neighbors = &graph.adjacency[current];
+        if neighbors.is_empty() {
+            break;
+        }
+
+        current = neighbors[rng.gen_range(0..neighbors.len())];
+        path.push(current);
+    }
+
+    path
+}
+
+/// Count how many times each degree appears
+fn degree_histogram(degrees: impl IntoIterator<Item = usize>) -> Vec<usize> {
+    let mut histogram = Vec::new();
+
+    for degree in degrees {
+        if degree >= histogram.len() {
+            histogram.resize(degree + 1, 0);
+        }
+        histogram[degree] += 1;
+    }
+
+    histogram
 }
 
 /// Helper function to generate a random graph for benchmarking
@@ -67,3 +524,264 @@ pub fn generate_graph(nodes: usize) -> Graph {
 
     graph
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_edge() {
+        let mut graph = Graph::new(3);
+        graph.add_edge(0, 1);
+        graph.add_edge(1, 2);
+
+        assert!(graph.has_edge(0, 1));
+        assert!(graph.has_edge(1, 2));
+        assert!(!graph.has_edge(1, 0)); // Edges are directed
+        assert!(!graph.has_edge(0, 2));
+        assert!(!graph.has_edge(5, 0)); // Out of range
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let mut graph = Graph::new(4);
+        graph.add_edge(0, 2);
+        graph.add_edge(0, 3);
+        graph.add_edge(2, 1);
+
+        assert_eq!(graph.neighbors(0).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(graph.neighbors(2).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(graph.neighbors(1).count(), 0);
+        assert_eq!(graph.neighbors(10).count(), 0); // Out of range
+    }
+
+    #[test]
+    fn test_dedup_edges() {
+        let mut graph = Graph::new(4);
+        graph.add_edge(0, 3);
+        graph.add_edge(0, 1);
+        graph.add_edge(0, 3);
+        graph.add_edge(0, 1);
+        graph.add_edge(0, 2);
+
+        graph.dedup_edges();
+
+        assert_eq!(graph.adjacency[0], vec![1, 2, 3]);
+        assert!(graph.has_edge(0, 3));
+    }
+
+    #[test]
+    fn test_degree_distribution() {
+        // 0 -> 1, 2, 3; 1 -> 2; 2 -> 0; 3 and 4 have no outgoing edges
+        let mut graph = Graph::new(5);
+        for (from, to) in [(0, 1), (0, 2), (0, 3), (1, 2), (2, 0)] {
+            graph.add_edge(from, to);
+        }
+
+        // Out-degrees: 3, 1, 1, 0, 0
+        assert_eq!(graph.degree_distribution(), vec![2, 2, 0, 1]);
+        // In-degrees: 1, 1, 2, 1, 0
+        assert_eq!(graph.in_degree_distribution(), vec![1, 3, 1]);
+
+        assert!(Graph::new(0).degree_distribution().is_empty());
+        assert_eq!(Graph::new(3).in_degree_distribution(), vec![3]);
+    }
+
+    #[test]
+    fn test_random_walk() {
+        let graph = generate_graph(100);
+
+        let walk = random_walk(&graph, 0, 50, 7);
+        assert_eq!(walk.len(), 51);
+        assert_eq!(walk[0], 0);
+        assert_eq!(walk, random_walk(&graph, 0, 50, 7));
+        assert_ne!(walk, random_walk(&graph, 0, 50, 8));
+        // Every step follows an edge
+        for pair in walk.windows(2) {
+            assert!(graph.has_edge(pair[0], pair[1]));
+        }
+
+        assert_eq!(random_walk(&graph, 3, 0, 7), vec![3]);
+        assert!(random_walk(&graph, 100, 50, 7).is_empty());
+    }
+
+    #[test]
+    fn test_random_walk_stops_at_dead_end() {
+        // 0 -> 1 -> 2, and 2 has no out-edges
+        let mut graph = Graph::new(3);
+        graph.add_edge(0, 1);
+        graph.add_edge(1, 2);
+
+        assert_eq!(random_walk(&graph, 0, 10, 42), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_bfs_undirected_follows_incoming_edges() {
+        // 0 -> 1, 2 -> 0, 3 -> 2: nodes 2 and 3 only link towards 0
+        let mut graph = Graph::new(5);
+        graph.add_edge(0, 1);
+        graph.add_edge(2, 0);
+        graph.add_edge(3, 2);
+
+        assert_eq!(bfs_naive(&graph, 0), vec![0, 1]);
+        assert_eq!(bfs_undirected(&graph, 0), vec![0, 1, 2, 3]);
+        // Node 4 has no edge at all
+        assert_eq!(bfs_undirected(&graph, 4), vec![4]);
+        assert!(bfs_undirected(&graph, 5).is_empty());
+
+        assert!(graph.reversed().has_edge(0, 2));
+        assert_eq!(graph.reversed().reversed(), graph);
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        // Cycles 0 -> 1 -> 2 -> 0 and 3 <-> 4, with a one-way bridge 2 -> 3,
+        // and node 5 only pointing into the first cycle
+        let mut graph = Graph::new(6);
+        for (from, to) in [(0, 1), (1, 2), (2, 0), (3, 4), (4, 3), (2, 3), (5, 0)] {
+            graph.add_edge(from, to);
+        }
+
+        assert_eq!(
+            graph.build_in_adjacency(),
+            vec![vec![2, 5], vec![0], vec![1], vec![2, 4], vec![3], vec![]]
+        );
+
+        let mut components = strongly_connected_components(&graph);
+        components.sort();
+        assert_eq!(components, vec![vec![0, 1, 2], vec![3, 4], vec![5]]);
+
+        assert!(strongly_connected_components(&Graph::new(0)).is_empty());
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let graph = generate_graph(1000);
+        let path = std::env::temp_dir().join("eurorust_bfs_round_trip.graph");
+        let path = path.to_str().unwrap();
+
+        graph.save_binary(path).unwrap();
+        let loaded = Graph::load_binary(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded, graph);
+    }
+
+    #[test]
+    fn test_load_binary_rejects_invalid_files() {
+        let mut huge_node_count = Vec::new();
+        write_varint(&mut huge_node_count, usize::MAX / 2).unwrap();
+        huge_node_count.extend_from_slice(&[0, 0]);
+
+        // 2 nodes, the first claiming far more edges than the file holds
+        let mut huge_edge_count = vec![2];
+        write_varint(&mut huge_edge_count, usize::MAX / 2).unwrap();
+        huge_edge_count.push(1);
+
+        // A node count whose only set bit is past usize::MAX, which would
+        // otherwise be read as an empty graph
+        let mut overflowing_node_count = vec![0x80; (usize::BITS / 7) as usize];
+        overflowing_node_count.push(0x02);
+
+        let truncated = vec![3, 1];
+        let missing_node = vec![2, 1, 5, 0];
+
+        for bytes in [
+            huge_node_count,
+            huge_edge_count,
+            overflowing_node_count,
+            truncated,
+            missing_node,
+        ] {
+            assert!(Graph::from_binary(&bytes).is_err(), "{bytes:?}");
+        }
+        assert_eq!(
+            Graph::from_binary(&[2, 1, 1, 0]).unwrap().adjacency,
+            vec![vec![1], vec![]]
+        );
+
+        let mut max = Vec::new();
+        write_varint(&mut max, usize::MAX).unwrap();
+        assert_eq!(read_varint(&mut max.as_slice()).unwrap(), usize::MAX);
+    }
+
+    #[test]
+    fn test_csr_conversion() {
+        let mut graph = Graph::new(3);
+        graph.add_edge(0, 1);
+        graph.add_edge(0, 2);
+        graph.add_edge(2, 1);
+
+        let csr = CsrGraph::from(&graph);
+
+        assert_eq!(csr.num_nodes(), 3);
+        assert_eq!(csr.offsets, vec![0, 2, 2, 3]);
+        assert_eq!(csr.edges, vec![1, 2, 1]);
+    }
+
+    #[test]
+    fn test_bfs_csr_matches_naive() {
+        let graph = generate_graph(1000);
+        let csr = CsrGraph::from(&graph);
+
+        assert_eq!(csr.bfs_csr(0), bfs_naive(&graph, 0));
+        assert_eq!(csr.bfs_csr(42), bfs_naive(&graph, 42));
+    }
+
+    #[test]
+    fn test_bfs_csr_start_out_of_range() {
+        let csr = CsrGraph::from(&generate_graph(10));
+
+        assert!(csr.bfs_csr(10).is_empty());
+        assert!(csr.bfs_csr(usize::MAX).is_empty());
+        assert!(CsrGraph::from(&Graph::new(0)).bfs_csr(0).is_empty());
+    }
+
+    #[test]
+    fn test_bfs_with_generations_matches_fresh_sets() {
+        let graph = generate_graph(200);
+        let csr = CsrGraph::from(&graph);
+        let mut visited = GenerationVisited::new(csr.num_nodes());
+
+        // Every start node reuses the same set, twice
+        for _ in 0..2 {
+            for start in 0..csr.num_nodes() {
+                assert_eq!(
+                    csr.bfs_with_generations(start, &mut visited),
+                    csr.bfs_csr(start),
+                    "Start node {start}"
+                );
+            }
+        }
+
+        let unknown = csr.num_nodes();
+        assert!(csr.bfs_with_generations(unknown, &mut visited).is_empty());
+    }
+
+    #[test]
+    fn test_generation_visited_wraps_around() {
+        let mut visited = GenerationVisited::new(3);
+        visited.generation = u32::MAX;
+
+        assert!(visited.insert(1));
+        assert!(!visited.insert(1));
+
+        visited.reset();
+        assert!(!visited.contains(1));
+        assert!(visited.insert(2));
+        assert!(visited.contains(2));
+    }
+
+    #[test]
+    fn test_varint_round_trip() {
+        let mut buffer = Vec::new();
+        for value in [0, 1, 127, 128, 300, usize::MAX] {
+            write_varint(&mut buffer, value).unwrap();
+        }
+
+        let mut reader = buffer.as_slice();
+        for value in [0, 1, 127, 128, 300, usize::MAX] {
+            assert_eq!(read_varint(&mut reader).unwrap(), value);
+        }
+    }
+}
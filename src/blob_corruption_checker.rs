@@ -1,68 +1,1847 @@
-use std::fs::File;
-use std::io::{BufReader, Read};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::simd::cmp::SimdPartialEq;
+use std::simd::{Mask, Simd};
+use std::time::{Duration, Instant};
+
+use image::GrayImage;
+use memmap2::Mmap;
+use rayon::prelude::*;
+
+use crate::helpers::build_image;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Corruption {
     /// Offset is aligned to the chunk_size boundary (e.g., 1KB = 1024 bytes)
     pub offset: u64,
-    /// Length is a multiple of chunk_size
+    /// Length is a multiple of chunk_size, except for a corruption reaching a
+    /// partial final chunk, which may be shorter
+    pub length: u64,
+}
+
+/// Where the time of a scan went, see [`find_corruptions_profiled`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanProfile {
+    /// Opening and memory-mapping both files
+    pub mmap: Duration,
+    /// Comparing the chunks
+    pub compare: Duration,
+    /// Merging mismatched chunks into corruptions
+    pub merge: Duration,
+}
+
+impl ScanProfile {
+    pub fn total(&self) -> Duration {
+        self.mmap + self.compare + self.merge
+    }
+}
+
+#[derive(Debug)]
+pub enum CorruptionError {
+    Io(io::Error),
+    /// The two inputs don't have the same number of bytes
+    LengthMismatch {
+        reference: u64,
+        corrupted: u64,
+    },
+}
+
+impl fmt::Display for CorruptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CorruptionError::Io(err) => write!(f, "I/O error: {err}"),
+            CorruptionError::LengthMismatch {
+                reference,
+                corrupted,
+            } => write!(
+                f,
+                "reference is {reference} bytes long but corrupted is {corrupted} bytes long"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CorruptionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CorruptionError::Io(err) => Some(err),
+            CorruptionError::LengthMismatch { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for CorruptionError {
+    fn from(err: io::Error) -> Self {
+        CorruptionError::Io(err)
+    }
+}
+
+/// A corruption that doesn't sit on chunk boundaries, see [`assert_aligned`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlignmentError {
+    /// The region doesn't start on a chunk boundary
+    MisalignedOffset { index: usize, offset: u64 },
+    /// The region isn't a whole number of chunks, and isn't the last one
+    MisalignedLength { index: usize, length: u64 },
+}
+
+impl fmt::Display for AlignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlignmentError::MisalignedOffset { index, offset } => {
+                write!(f, "corruption {index} starts at unaligned offset {offset}")
+            }
+            AlignmentError::MisalignedLength { index, length } => {
+                write!(f, "corruption {index} has unaligned length {length}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AlignmentError {}
+
+/// How [`find_corruptions`] compares the chunks of the two files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanStrategy {
+    /// One thread reading both files chunk by chunk, see [`find_corruptions_sequential`]
+    Sequential,
+    /// Memory-mapped files compared in [`DEFAULT_WORK_BLOCK_SIZE`] blocks by
+    /// rayon, see [`find_corruptions_parallel`]
+    Parallel,
+    /// One thread comparing memory-mapped 1 MB super-chunks with SIMD, and
+    /// subdividing the ones that differ, see [`find_corruptions_simd`]
+    Simd,
+    /// Same as `Simd`, with the super-chunks compared by rayon, see
+    /// [`find_corruptions_simd_parallel`]
+    SimdParallel,
+}
+
+/// Work block size used by [`ScanStrategy::Parallel`]
+pub const DEFAULT_WORK_BLOCK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Find the corrupted regions of `corrupted_path`, comparing chunks with `strategy`
+///
+/// Every strategy returns the same corruptions, they only differ in speed.
+pub fn find_corruptions(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+    strategy: ScanStrategy,
+) -> Vec<Corruption> {
+    find_corruptions_with_work_block_size(
+        reference_path,
+        corrupted_path,
+        chunk_size,
+        strategy,
+        DEFAULT_WORK_BLOCK_SIZE,
+    )
+}
+
+/// Same as [`find_corruptions`], with the work block size of [`ScanStrategy::Parallel`]
+fn find_corruptions_with_work_block_size(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+    strategy: ScanStrategy,
+    work_block_size: usize,
+) -> Vec<Corruption> {
+    let mismatched_chunks = match strategy {
+        ScanStrategy::Sequential => {
+            mismatched_chunks_read(reference_path, corrupted_path, chunk_size)
+        }
+        ScanStrategy::Parallel => {
+            let (ref_map, corrupt_map) = map_files(reference_path, corrupted_path);
+            mismatched_chunks_parallel(&ref_map, &corrupt_map, chunk_size, work_block_size)
+        }
+        ScanStrategy::Simd => {
+            let (ref_map, corrupt_map) = map_files(reference_path, corrupted_path);
+            mismatched_chunks_simd(&ref_map, &corrupt_map, chunk_size, SUPER_CHUNK_SIZE)
+        }
+        ScanStrategy::SimdParallel => {
+            let (ref_map, corrupt_map) = map_files(reference_path, corrupted_path);
+            mismatched_chunks_simd_parallel(&ref_map, &corrupt_map, chunk_size, SUPER_CHUNK_SIZE)
+        }
+    };
+
+    merge_chunks(mismatched_chunks)
+}
+
+/// Memory-map a whole file
+fn map_file(path: &str) -> io::Result<Mmap> {
+    let file = File::open(path)?;
+    unsafe { Mmap::map(&file) }
+}
+
+/// Memory-map both files, which must have the same length
+fn map_files(reference_path: &str, corrupted_path: &str) -> (Mmap, Mmap) {
+    let ref_map = map_file(reference_path).unwrap();
+    let corrupt_map = map_file(corrupted_path).unwrap();
+    assert_eq!(
+        ref_map.len(),
+        corrupt_map.len(),
+        "Reference and corrupted files must have the same length"
+    );
+
+    (ref_map, corrupt_map)
+}
+
+/// `(offset, length)` of the differing `chunk_size` chunks of two slices
+///
+/// Offsets are relative to the start of the slices. The final chunk may be
+/// partial, and counts for its actual length.
+fn mismatched_chunks_mmap<'a>(
+    reference: &'a [u8],
+    corrupted: &'a [u8],
+    chunk_size: usize,
+) -> impl Iterator<Item = (u64, u64)> + 'a {
+    reference
+        .chunks(chunk_size)
+        .zip(corrupted.chunks(chunk_size))
+        .enumerate()
+        .filter(|(_, (ref_chunk, corrupt_chunk))| ref_chunk != corrupt_chunk)
+        .map(move |(i, (ref_chunk, _))| ((i * chunk_size) as u64, ref_chunk.len() as u64))
+}
+
+/// Shift the offsets of [`mismatched_chunks_mmap`] by `base`, for slices that
+/// don't start at the beginning of the file
+fn offset_chunks(
+    base: u64,
+    chunks: impl Iterator<Item = (u64, u64)>,
+) -> impl Iterator<Item = (u64, u64)> {
+    chunks.map(move |(offset, length)| (base + offset, length))
+}
+
+pub fn find_corruptions_sequential(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+) -> Vec<Corruption> {
+    find_corruptions(
+        reference_path,
+        corrupted_path,
+        chunk_size,
+        ScanStrategy::Sequential,
+    )
+}
+
+/// `(offset, length)` of the differing chunks, reading both files chunk by chunk
+fn mismatched_chunks_read(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+) -> Vec<(u64, u64)> {
+    let ref_file = BufReader::new(File::open(reference_path).unwrap());
+    let corrupt_file = BufReader::new(File::open(corrupted_path).unwrap());
+
+    mismatched_chunks_streams(ref_file, corrupt_file, chunk_size).unwrap()
+}
+
+/// Same scan as [`find_corruptions_sequential`] over memory-mapped files
+///
+/// Still a single thread, so comparing the two isolates the cost of `read`
+/// copies against page faults on the mappings.
+pub fn find_corruptions_sequential_mmap(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+) -> Vec<Corruption> {
+    let (ref_map, corrupt_map) = map_files(reference_path, corrupted_path);
+
+    merge_chunks(mismatched_chunks_mmap(&ref_map, &corrupt_map, chunk_size))
+}
+
+/// Compare `reference[i]` against `corrupted[i + shift]`, for data shifted by a known amount
+///
+/// An insertion (positive `shift`) or deletion (negative `shift`) at the
+/// start of the corrupted file would otherwise make every following chunk
+/// differ. The files may have different lengths: only the range where both
+/// sides exist is compared, in chunks starting at the beginning of that
+/// range. Offsets are in the reference file. A shift past the end of either
+/// file leaves nothing to compare, and finds no corruption.
+pub fn find_corruptions_with_shift(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+    shift: i64,
+) -> Vec<Corruption> {
+    let ref_map = map_file(reference_path).unwrap();
+    let corrupt_map = map_file(corrupted_path).unwrap();
+
+    // Overlap: both i and i + shift must be in bounds. Working on the
+    // magnitude of the shift keeps any i64, even i64::MIN, from overflowing
+    let (ref_start, corrupt_start) = if shift >= 0 {
+        (0, shift.unsigned_abs())
+    } else {
+        (shift.unsigned_abs(), 0)
+    };
+    let (ref_len, corrupt_len) = (ref_map.len() as u64, corrupt_map.len() as u64);
+    if ref_start >= ref_len || corrupt_start >= corrupt_len {
+        return Vec::new();
+    }
+    let overlap = (ref_len - ref_start).min(corrupt_len - corrupt_start);
+
+    let reference = &ref_map[ref_start as usize..(ref_start + overlap) as usize];
+    let corrupted = &corrupt_map[corrupt_start as usize..(corrupt_start + overlap) as usize];
+
+    merge_chunks(offset_chunks(
+        ref_start,
+        mismatched_chunks_mmap(reference, corrupted, chunk_size),
+    ))
+}
+
+/// Same scan as [`find_corruptions_sequential`], timing each phase
+///
+/// Depending on the input, setting up the mappings or merging a long list of
+/// mismatched chunks can cost more than the comparison itself.
+pub fn find_corruptions_profiled(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+) -> (Vec<Corruption>, ScanProfile) {
+    let start = Instant::now();
+    let (ref_map, corrupt_map) = map_files(reference_path, corrupted_path);
+    let mmap = start.elapsed();
+
+    let start = Instant::now();
+    let mismatched_chunks: Vec<(u64, u64)> =
+        mismatched_chunks_mmap(&ref_map, &corrupt_map, chunk_size).collect();
+    let compare = start.elapsed();
+
+    let start = Instant::now();
+    let corruptions = merge_chunks(mismatched_chunks);
+    let merge = start.elapsed();
+
+    (
+        corruptions,
+        ScanProfile {
+            mmap,
+            compare,
+            merge,
+        },
+    )
+}
+
+/// Offsets of every `chunk_size` chunk that differs, in increasing order
+///
+/// Unlike [`find_corruptions_sequential`], consecutive chunks are not merged
+/// into a single [`Corruption`], so callers can aggregate them however they want.
+pub fn find_mismatched_chunks(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+) -> Vec<u64> {
+    mismatched_chunks_read(reference_path, corrupted_path, chunk_size)
+        .into_iter()
+        .map(|(offset, _)| offset)
+        .collect()
+}
+
+/// Parallel scan over memory-mapped files
+///
+/// `chunk_size` is the reporting granularity, while `work_block_size` is the
+/// amount of data handed to each rayon task. Using 1 KB chunks as the unit of
+/// parallelism would create hundreds of thousands of tiny tasks whose
+/// scheduling overhead dwarfs the comparison itself, so each task scans a
+/// larger block (e.g. 4 MB) chunk by chunk instead.
+/// `work_block_size` is rounded up to a multiple of `chunk_size` so no chunk
+/// straddles two blocks.
+pub fn find_corruptions_parallel(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+    work_block_size: usize,
+) -> Vec<Corruption> {
+    find_corruptions_with_work_block_size(
+        reference_path,
+        corrupted_path,
+        chunk_size,
+        ScanStrategy::Parallel,
+        work_block_size,
+    )
+}
+
+/// `(offset, length)` of the differing chunks, one rayon task per work block
+///
+/// `work_block_size` is rounded up to a multiple of `chunk_size`.
+fn mismatched_chunks_parallel(
+    reference: &[u8],
+    corrupted: &[u8],
+    chunk_size: usize,
+    work_block_size: usize,
+) -> Vec<(u64, u64)> {
+    par_work_blocks(reference, corrupted, chunk_size, work_block_size)
+        .flat_map_iter(|(block_offset, ref_block, corrupt_block)| {
+            offset_chunks(
+                block_offset,
+                mismatched_chunks_mmap(ref_block, corrupt_block, chunk_size),
+            )
+        })
+        .collect()
+}
+
+/// Both slices cut into blocks for rayon, as `(offset, reference block, corrupted block)`
+///
+/// `work_block_size` is rounded up to a multiple of `chunk_size` so no chunk
+/// straddles two blocks.
+fn par_work_blocks<'a>(
+    reference: &'a [u8],
+    corrupted: &'a [u8],
+    chunk_size: usize,
+    work_block_size: usize,
+) -> impl IndexedParallelIterator<Item = (u64, &'a [u8], &'a [u8])> {
+    let work_block_size = work_block_size.div_ceil(chunk_size).max(1) * chunk_size;
+
+    reference
+        .par_chunks(work_block_size)
+        .zip(corrupted.par_chunks(work_block_size))
+        .enumerate()
+        .map(move |(block_index, (ref_block, corrupt_block))| {
+            (
+                (block_index * work_block_size) as u64,
+                ref_block,
+                corrupt_block,
+            )
+        })
+}
+
+/// Number of chunks that differ, without collecting or merging their offsets
+///
+/// A partial final chunk counts as one chunk.
+pub fn count_corrupted_chunks(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+) -> u64 {
+    let (ref_map, corrupt_map) = map_files(reference_path, corrupted_path);
+
+    par_work_blocks(&ref_map, &corrupt_map, chunk_size, DEFAULT_WORK_BLOCK_SIZE)
+        .map(|(_, ref_block, corrupt_block)| {
+            mismatched_chunks_mmap(ref_block, corrupt_block, chunk_size).count() as u64
+        })
+        .sum()
+}
+
+/// Size of the blocks compared as a whole by [`find_corruptions_simd`]
+const SUPER_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// SIMD scan, skipping identical 1 MB super-chunks in one comparison
+///
+/// Corruptions are rare, so almost every super-chunk matches: comparing it
+/// with [`chunks_equal_simd`] in one go avoids splitting it into
+/// `chunk_size` slices. Only a super-chunk that differs is subdivided to find
+/// which chunks are corrupted.
+pub fn find_corruptions_simd(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+) -> Vec<Corruption> {
+    find_corruptions(
+        reference_path,
+        corrupted_path,
+        chunk_size,
+        ScanStrategy::Simd,
+    )
+}
+
+/// Same as [`find_corruptions_simd`], with the super-chunks compared by rayon
+pub fn find_corruptions_simd_parallel(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+) -> Vec<Corruption> {
+    find_corruptions(
+        reference_path,
+        corrupted_path,
+        chunk_size,
+        ScanStrategy::SimdParallel,
+    )
+}
+
+/// `(offset, length)` of the differing chunks, comparing whole super-chunks first
+///
+/// `super_chunk_size` is rounded up to a multiple of `chunk_size` so no chunk
+/// straddles two super-chunks.
+fn mismatched_chunks_simd(
+    reference: &[u8],
+    corrupted: &[u8],
+    chunk_size: usize,
+    super_chunk_size: usize,
+) -> Vec<(u64, u64)> {
+    let super_chunk_size = super_chunk_size.div_ceil(chunk_size).max(1) * chunk_size;
+
+    reference
+        .chunks(super_chunk_size)
+        .zip(corrupted.chunks(super_chunk_size))
+        .enumerate()
+        .filter(|(_, (ref_block, corrupt_block))| {
+            !chunks_equal_simd::<64>(ref_block, corrupt_block)
+        })
+        .flat_map(|(block_index, (ref_block, corrupt_block))| {
+            let block_offset = (block_index * super_chunk_size) as u64;
+            offset_chunks(
+                block_offset,
+                mismatched_chunks_mmap(ref_block, corrupt_block, chunk_size),
+            )
+        })
+        .collect()
+}
+
+/// Same as [`mismatched_chunks_simd`], with the super-chunks compared by rayon
+fn mismatched_chunks_simd_parallel(
+    reference: &[u8],
+    corrupted: &[u8],
+    chunk_size: usize,
+    super_chunk_size: usize,
+) -> Vec<(u64, u64)> {
+    par_work_blocks(reference, corrupted, chunk_size, super_chunk_size)
+        .filter(|(_, ref_block, corrupt_block)| !chunks_equal_simd::<64>(ref_block, corrupt_block))
+        .flat_map_iter(|(block_offset, ref_block, corrupt_block)| {
+            offset_chunks(
+                block_offset,
+                mismatched_chunks_mmap(ref_block, corrupt_block, chunk_size),
+            )
+        })
+        .collect()
+}
+
+/// Scan from `resume_from` to the end of the files, for checkpointed scans
+///
+/// Returns the corruptions found and the last scanned offset, which a caller
+/// can persist to restart an interrupted scan. `resume_from` is floored to a
+/// chunk boundary.
+pub fn find_corruptions_resumable(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+    resume_from: u64,
+) -> (Vec<Corruption>, u64) {
+    find_corruptions_resumable_bounded(
+        reference_path,
+        corrupted_path,
+        chunk_size,
+        resume_from,
+        u64::MAX,
+    )
+}
+
+/// Same as [`find_corruptions_resumable`], scanning at most `max_bytes`
+///
+/// Returns the corruptions found and the offset to resume from next time,
+/// equal to the file length once the scan is complete. `max_bytes` is
+/// rounded up to whole chunks (at least one). A corruption straddling two
+/// calls is reported in two parts, [`merge_corruptions`] joins them back.
+pub fn find_corruptions_resumable_bounded(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+    resume_from: u64,
+    max_bytes: u64,
+) -> (Vec<Corruption>, u64) {
+    let (ref_map, corrupt_map) = map_files(reference_path, corrupted_path);
+
+    let chunk = chunk_size as u64;
+    let len = ref_map.len() as u64;
+    // The end of the file is a valid resume point even if it's not a chunk boundary
+    let start = if resume_from >= len {
+        len
+    } else {
+        resume_from / chunk * chunk
+    };
+    let budget = max_bytes.div_ceil(chunk).max(1).saturating_mul(chunk);
+    let end = start.saturating_add(budget).min(len);
+
+    let range = start as usize..end as usize;
+    let mismatched_chunks =
+        mismatched_chunks_mmap(&ref_map[range.clone()], &corrupt_map[range], chunk_size);
+
+    (merge_chunks(offset_chunks(start, mismatched_chunks)), end)
+}
+
+/// Whether two files have exactly the same content
+///
+/// A length mismatch answers `false` without reading anything, otherwise
+/// chunks are compared with [`chunks_equal_simd`] until the first difference.
+/// Nothing is allocated or merged, so files that differ early are rejected
+/// much faster than with a full scan.
+pub fn files_identical(
+    path_a: &str,
+    path_b: &str,
+    chunk_size: usize,
+) -> Result<bool, CorruptionError> {
+    if std::fs::metadata(path_a)?.len() != std::fs::metadata(path_b)?.len() {
+        return Ok(false);
+    }
+
+    let map_a = map_file(path_a)?;
+    let map_b = map_file(path_b)?;
+
+    Ok(map_a
+        .chunks(chunk_size)
+        .zip(map_b.chunks(chunk_size))
+        .all(|(chunk_a, chunk_b)| chunks_equal_simd::<64>(chunk_a, chunk_b)))
+}
+
+/// Same as [`find_corruptions_sequential_mmap`], treating bytes inside `ignore_ranges` as matching
+///
+/// Each range is a half-open `(start, end)` byte range, for volatile regions
+/// like timestamps or checksums that legitimately differ. A chunk is only
+/// reported if it differs outside of every ignored range.
+pub fn find_corruptions_masked(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+    ignore_ranges: &[(u64, u64)],
+) -> Vec<Corruption> {
+    let (ref_map, corrupt_map) = map_files(reference_path, corrupted_path);
+
+    let is_ignored = |offset: u64| {
+        ignore_ranges
+            .iter()
+            .any(|&(start, end)| (start..end).contains(&offset))
+    };
+
+    // Only mismatched chunks, which are rare, are checked byte by byte
+    let differs_outside_ranges = |&(offset, length): &(u64, u64)| {
+        let range = offset as usize..(offset + length) as usize;
+        ref_map[range.clone()]
+            .iter()
+            .zip(&corrupt_map[range])
+            .enumerate()
+            .any(|(j, (ref_byte, corrupt_byte))| {
+                ref_byte != corrupt_byte && !is_ignored(offset + j as u64)
+            })
+    };
+
+    let mismatched_chunks =
+        mismatched_chunks_mmap(&ref_map, &corrupt_map, chunk_size).filter(differs_outside_ranges);
+
+    merge_chunks(mismatched_chunks)
+}
+
+/// Every differing byte of a corruption, as `(offset, reference byte, corrupted byte)`
+///
+/// The drill-down once a scan has located a corrupted region. The region is
+/// cut at the end of the files.
+pub fn corruption_byte_diffs(
+    reference_path: &str,
+    corrupted_path: &str,
+    corruption: &Corruption,
+) -> Vec<(u64, u8, u8)> {
+    let ref_map = map_file(reference_path).unwrap();
+    let corrupt_map = map_file(corrupted_path).unwrap();
+
+    let len = ref_map.len().min(corrupt_map.len()) as u64;
+    let start = corruption.offset.min(len) as usize;
+    let end = (corruption.offset + corruption.length).min(len) as usize;
+
+    ref_map[start..end]
+        .iter()
+        .zip(&corrupt_map[start..end])
+        .enumerate()
+        .filter(|(_, (ref_byte, corrupt_byte))| ref_byte != corrupt_byte)
+        .map(|(i, (&ref_byte, &corrupt_byte))| ((start + i) as u64, ref_byte, corrupt_byte))
+        .collect()
+}
+
+/// The longest run of consecutive matching chunks, as `(offset, length)` in bytes
+///
+/// Ties go to the earliest run. Returns `(0, 0)` if every chunk differs.
+pub fn longest_matching_run(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+) -> (u64, u64) {
+    let (ref_map, corrupt_map) = map_files(reference_path, corrupted_path);
+
+    let mismatches = mismatch_bitmap(&ref_map, &corrupt_map, chunk_size);
+
+    // (first chunk, chunk count) of the best run so far and of the current one
+    let mut best = (0, 0);
+    let mut current = (0, 0);
+    for (i, &mismatch) in mismatches.iter().enumerate() {
+        if mismatch {
+            current = (i + 1, 0);
+        } else {
+            current.1 += 1;
+            if current.1 > best.1 {
+                best = current;
+            }
+        }
+    }
+
+    let offset = best.0 * chunk_size;
+    let end = ((best.0 + best.1) * chunk_size).min(ref_map.len());
+    (offset as u64, end.saturating_sub(offset) as u64)
+}
+
+/// One flag per `chunk_size` chunk, set if the chunk differs
+fn mismatch_bitmap(reference: &[u8], corrupted: &[u8], chunk_size: usize) -> Vec<bool> {
+    reference
+        .chunks(chunk_size)
+        .zip(corrupted.chunks(chunk_size))
+        .map(|(ref_chunk, corrupt_chunk)| ref_chunk != corrupt_chunk)
+        .collect()
+}
+
+/// Re-check only the chunks overlapping `suspect_regions`
+///
+/// For incremental verification: once a first scan has narrowed down where
+/// damage can happen, later scans skip every other chunk. Regions don't need
+/// to be chunk-aligned, sorted or disjoint; each chunk they touch is compared
+/// once. Corruptions outside the suspect regions are not reported.
+pub fn rescan_corruptions(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+    suspect_regions: &[Corruption],
+) -> Vec<Corruption> {
+    let (ref_map, corrupt_map) = map_files(reference_path, corrupted_path);
+
+    let chunk_count = ref_map.len().div_ceil(chunk_size);
+    let mut suspect_chunks: Vec<usize> = suspect_regions
+        .iter()
+        .filter(|region| region.length > 0)
+        .flat_map(|region| {
+            let first = region.offset as usize / chunk_size;
+            let end = (region.offset + region.length) as usize;
+            first..end.div_ceil(chunk_size).min(chunk_count)
+        })
+        .collect();
+    suspect_chunks.sort_unstable();
+    suspect_chunks.dedup();
+
+    let mismatched_chunks = suspect_chunks.into_iter().filter_map(|chunk| {
+        let start = chunk * chunk_size;
+        let end = (start + chunk_size).min(ref_map.len());
+
+        (ref_map[start..end] != corrupt_map[start..end])
+            .then_some((start as u64, (end - start) as u64))
+    });
+
+    merge_chunks(mismatched_chunks)
+}
+
+/// Check several candidate files against the same reference
+///
+/// The reference is memory-mapped once and shared by all the candidates, which
+/// are scanned in parallel (one rayon task per candidate). Results are returned
+/// in the order of `candidates`, paired with the candidate path.
+pub fn find_corruptions_batch(
+    reference_path: &str,
+    candidates: &[&str],
+    chunk_size: usize,
+) -> Vec<(String, Vec<Corruption>)> {
+    let ref_map = map_file(reference_path).unwrap();
+
+    candidates
+        .par_iter()
+        .map(|&candidate_path| {
+            let candidate_map = map_file(candidate_path).unwrap();
+            assert_eq!(
+                ref_map.len(),
+                candidate_map.len(),
+                "Reference and {candidate_path} must have the same length"
+            );
+
+            let mismatched_chunks = mismatched_chunks_mmap(&ref_map, &candidate_map, chunk_size);
+            (candidate_path.to_string(), merge_chunks(mismatched_chunks))
+        })
+        .collect()
+}
+
+/// Render the damage of a corrupted file as a grayscale image
+///
+/// Each pixel is one `chunk_size` chunk, laid out row-major `width` chunks per
+/// row. Its brightness is proportional to the fraction of differing bytes in
+/// the chunk: clean chunks are black, fully rewritten chunks are white. Pixels
+/// past the last chunk stay black.
+pub fn corruption_heatmap(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+    width: u32,
+) -> GrayImage {
+    assert!(width > 0, "Heatmap width must be at least 1");
+
+    let (ref_map, corrupt_map) = map_files(reference_path, corrupted_path);
+
+    let chunks = ref_map.len().div_ceil(chunk_size);
+    let height = chunks.div_ceil(width as usize).max(1) as u32;
+    let mut pixels = vec![0u8; width as usize * height as usize];
+
+    let chunk_pairs = ref_map
+        .chunks(chunk_size)
+        .zip(corrupt_map.chunks(chunk_size));
+    for (pixel, (ref_chunk, corrupt_chunk)) in pixels.iter_mut().zip(chunk_pairs) {
+        let differing = ref_chunk
+            .iter()
+            .zip(corrupt_chunk)
+            .filter(|(a, b)| a != b)
+            .count();
+        *pixel = (differing * 255).div_ceil(ref_chunk.len()) as u8;
+    }
+
+    build_image(width, height, pixels).expect("heatmap pixels are sized from its dimensions")
+}
+
+/// Verify a local file against a reference streamed from `reference`
+///
+/// The local file is memory-mapped, while the reference is pulled from the
+/// reader one chunk at a time, so it never has to be stored on disk (e.g.
+/// while it is being downloaded).
+pub fn verify_stream<R: Read>(
+    corrupted_path: &str,
+    mut reference: R,
+    chunk_size: usize,
+) -> Result<Vec<Corruption>, CorruptionError> {
+    let corrupt_map = map_file(corrupted_path)?;
+
+    let mut ref_buffer = vec![0u8; chunk_size];
+    let mut mismatched_chunks = Vec::new();
+    let mut offset = 0usize;
+
+    loop {
+        let n = read_chunk(&mut reference, &mut ref_buffer)?;
+        if n == 0 {
+            break;
+        }
+
+        let Some(corrupt_chunk) = corrupt_map.get(offset..offset + n) else {
+            // The reference keeps going after the end of the local file
+            let remaining = io::copy(&mut reference, &mut io::sink())?;
+            return Err(CorruptionError::LengthMismatch {
+                reference: (offset + n) as u64 + remaining,
+                corrupted: corrupt_map.len() as u64,
+            });
+        };
+
+        if ref_buffer[..n] != *corrupt_chunk {
+            mismatched_chunks.push((offset as u64, n as u64));
+        }
+        offset += n;
+    }
+
+    if offset != corrupt_map.len() {
+        return Err(CorruptionError::LengthMismatch {
+            reference: offset as u64,
+            corrupted: corrupt_map.len() as u64,
+        });
+    }
+
+    Ok(merge_chunks(mismatched_chunks))
+}
+
+/// Compare two zstd-compressed files, decompressing them on the fly
+///
+/// Compressed files can't be memory-mapped or seeked, so both decompressed
+/// streams are read side by side one chunk at a time, see [`compare_streams`].
+pub fn find_corruptions_zstd(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+) -> Result<Vec<Corruption>, CorruptionError> {
+    let reference = zstd::Decoder::new(File::open(reference_path)?)?;
+    let corrupted = zstd::Decoder::new(File::open(corrupted_path)?)?;
+
+    compare_streams(reference, corrupted, chunk_size)
+}
+
+/// Compare two readers chunk by chunk
+///
+/// Neither input has to be stored anywhere: only one chunk of each is kept in
+/// memory at a time.
+pub fn compare_streams<R: Read, C: Read>(
+    reference: R,
+    corrupted: C,
+    chunk_size: usize,
+) -> Result<Vec<Corruption>, CorruptionError> {
+    Ok(merge_chunks(mismatched_chunks_streams(
+        reference, corrupted, chunk_size,
+    )?))
+}
+
+/// `(offset, length)` of the differing chunks of two readers, see [`compare_streams`]
+fn mismatched_chunks_streams<R: Read, C: Read>(
+    mut reference: R,
+    mut corrupted: C,
+    chunk_size: usize,
+) -> Result<Vec<(u64, u64)>, CorruptionError> {
+    let mut ref_buffer = vec![0u8; chunk_size];
+    let mut corrupt_buffer = vec![0u8; chunk_size];
+    let mut mismatched_chunks = Vec::new();
+    let mut offset = 0u64;
+
+    loop {
+        // A plain `read` may return less than a chunk in the middle of the
+        // stream (e.g. at a BufReader refill), which would shift every later
+        // chunk: only the final chunk may be short
+        let ref_n = read_chunk(&mut reference, &mut ref_buffer)?;
+        let corrupt_n = read_chunk(&mut corrupted, &mut corrupt_buffer)?;
+
+        if ref_n != corrupt_n {
+            // One of the streams ended early, count what's left of the other one
+            let reference_len = offset + ref_n as u64 + io::copy(&mut reference, &mut io::sink())?;
+            let corrupted_len =
+                offset + corrupt_n as u64 + io::copy(&mut corrupted, &mut io::sink())?;
+            return Err(CorruptionError::LengthMismatch {
+                reference: reference_len,
+                corrupted: corrupted_len,
+            });
+        }
+        if ref_n == 0 {
+            break;
+        }
+
+        // The final chunk counts for its actual length
+        if ref_buffer[..ref_n] != corrupt_buffer[..ref_n] {
+            mismatched_chunks.push((offset, ref_n as u64));
+        }
+        offset += ref_n as u64;
+    }
+
+    Ok(mismatched_chunks)
+}
+
+/// CRC32 of every `chunk_size` chunk of `data`, computed in parallel
+///
+/// The last checksum covers the final partial chunk, if any.
+pub fn chunk_crcs(data: &[u8], chunk_size: usize) -> Vec<u32> {
+    data.par_chunks(chunk_size).map(crc32fast::hash).collect()
+}
+
+/// Per-chunk checksums of a reference blob, see [`verify_against_crcs`]
+///
+/// Storing a manifest instead of a full copy of the reference is enough to
+/// locate corrupted chunks, at 4 bytes per chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrcManifest {
+    pub chunk_size: usize,
+    /// Length of the reference in bytes
     pub length: u64,
+    pub crcs: Vec<u32>,
+}
+
+impl CrcManifest {
+    /// Identifies manifest files, followed by the format version
+    const MAGIC: &[u8; 8] = b"BLOBCRC1";
+
+    pub fn from_bytes(data: &[u8], chunk_size: usize) -> Self {
+        CrcManifest {
+            chunk_size,
+            length: data.len() as u64,
+            crcs: chunk_crcs(data, chunk_size),
+        }
+    }
+
+    /// Write the manifest: magic, chunk size and length as little-endian u64,
+    /// then every checksum as a little-endian u32
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(Self::MAGIC)?;
+        writer.write_all(&(self.chunk_size as u64).to_le_bytes())?;
+        writer.write_all(&self.length.to_le_bytes())?;
+        for crc in &self.crcs {
+            writer.write_all(&crc.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    /// Read a manifest written by [`CrcManifest::write_to`]
+    pub fn read_from(mut reader: impl Read) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut header = [0u8; 24];
+        reader.read_exact(&mut header)?;
+        if &header[..8] != Self::MAGIC {
+            return Err(invalid("not a blob manifest"));
+        }
+        let chunk_size = u64::from_le_bytes(header[8..16].try_into().unwrap());
+        let length = u64::from_le_bytes(header[16..24].try_into().unwrap());
+        if chunk_size == 0 {
+            return Err(invalid("chunk size of 0"));
+        }
+        let chunk_size =
+            usize::try_from(chunk_size).map_err(|_| invalid("chunk size too large"))?;
+        let body_len = length
+            .div_ceil(chunk_size as u64)
+            .checked_mul(4)
+            .ok_or_else(|| invalid("length too large"))?;
+
+        // Never read more than the header announces, whatever the input holds
+        let mut body = Vec::new();
+        reader.by_ref().take(body_len).read_to_end(&mut body)?;
+        let has_trailing_bytes = reader.read(&mut [0u8])? > 0;
+        if body.len() as u64 != body_len || has_trailing_bytes {
+            return Err(invalid("checksum count doesn't match the length"));
+        }
+
+        let crcs = body
+            .chunks_exact(4)
+            .map(|crc| u32::from_le_bytes(crc.try_into().unwrap()))
+            .collect();
+
+        Ok(CrcManifest {
+            chunk_size,
+            length,
+            crcs,
+        })
+    }
+}
+
+/// Check a file against a [`CrcManifest`] of its reference
+///
+/// Only chunks whose checksum differs are reported, so a corruption that
+/// happens to keep the same CRC32 goes unnoticed.
+pub fn verify_against_crcs(
+    corrupted_path: &str,
+    manifest: &CrcManifest,
+) -> Result<Vec<Corruption>, CorruptionError> {
+    let corrupt_map = map_file(corrupted_path)?;
+
+    if corrupt_map.len() as u64 != manifest.length {
+        return Err(CorruptionError::LengthMismatch {
+            reference: manifest.length,
+            corrupted: corrupt_map.len() as u64,
+        });
+    }
+
+    let chunk_size = manifest.chunk_size;
+    let mismatched_chunks: Vec<(u64, u64)> = corrupt_map
+        .par_chunks(chunk_size)
+        .zip(manifest.crcs.par_iter())
+        .enumerate()
+        .filter(|(_, (chunk, crc))| crc32fast::hash(chunk) != **crc)
+        .map(|(i, (chunk, _))| ((i * chunk_size) as u64, chunk.len() as u64))
+        .collect();
+
+    Ok(merge_chunks(mismatched_chunks))
+}
+
+/// Merge corruptions separated by fewer than `max_gap_bytes` bytes
+///
+/// Works on the output of any scanner: the input doesn't have to be sorted,
+/// and overlapping or adjacent corruptions are always merged. The bytes of a
+/// gap that gets merged are included in the resulting corruption.
+pub fn merge_corruptions(corruptions: &[Corruption], max_gap_bytes: u64) -> Vec<Corruption> {
+    let mut sorted = corruptions.to_vec();
+    sorted.sort_by_key(|corruption| corruption.offset);
+
+    let mut merged: Vec<Corruption> = Vec::with_capacity(sorted.len());
+
+    for corruption in sorted {
+        match merged.last_mut() {
+            Some(last) => {
+                let last_end = last.offset + last.length;
+                let gap = corruption.offset.saturating_sub(last_end);
+
+                if corruption.offset <= last_end || gap < max_gap_bytes {
+                    let end = last_end.max(corruption.offset + corruption.length);
+                    last.length = end - last.offset;
+                } else {
+                    merged.push(corruption);
+                }
+            }
+            None => merged.push(corruption),
+        }
+    }
+
+    merged
+}
+
+/// Check that every corruption starts and ends on a `chunk_size` boundary
+///
+/// The last region may be shorter than a chunk, since scanners report the
+/// tail of a file that isn't a multiple of `chunk_size` as is. Returns the
+/// first offending region instead of panicking.
+pub fn assert_aligned(corruptions: &[Corruption], chunk_size: u64) -> Result<(), AlignmentError> {
+    assert!(chunk_size > 0, "chunk_size must be positive");
+
+    for (index, corruption) in corruptions.iter().enumerate() {
+        if corruption.offset % chunk_size != 0 {
+            return Err(AlignmentError::MisalignedOffset {
+                index,
+                offset: corruption.offset,
+            });
+        }
+        let is_last = index + 1 == corruptions.len();
+        if corruption.length % chunk_size != 0 && !is_last {
+            return Err(AlignmentError::MisalignedLength {
+                index,
+                length: corruption.length,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Total number of corrupted bytes, assuming the corruptions don't overlap
+///
+/// Scanner results never overlap, other lists can go through
+/// [`merge_corruptions`] first.
+pub fn total_corrupted_bytes(corruptions: &[Corruption]) -> u64 {
+    corruptions.iter().map(|corruption| corruption.length).sum()
+}
+
+/// Fraction of a `file_len`-byte file covered by corruptions, from 0.0 to 1.0
+///
+/// An empty file has nothing to corrupt, so its ratio is 0.0.
+pub fn corruption_ratio(corruptions: &[Corruption], file_len: u64) -> f64 {
+    if file_len == 0 {
+        return 0.0;
+    }
+
+    total_corrupted_bytes(corruptions) as f64 / file_len as f64
+}
+
+/// Identifies patch files written by [`write_patch`], followed by the format version
+const PATCH_MAGIC: &[u8; 8] = b"BLOBPTC1";
+
+/// Write the reference bytes of every corrupted region of `corrupted_path`
+///
+/// The patch starts with a magic number, followed by one record per
+/// corruption: offset and length as little-endian u64, then the reference
+/// bytes. Sending it and calling [`apply_patch`] on the other side repairs the
+/// corrupted copy without transferring the whole reference.
+/// Returns the patched regions.
+pub fn write_patch(
+    reference_path: &str,
+    corrupted_path: &str,
+    chunk_size: usize,
+    out: &mut impl Write,
+) -> Result<Vec<Corruption>, CorruptionError> {
+    let ref_map = map_file(reference_path)?;
+    let corrupt_map = map_file(corrupted_path)?;
+
+    if ref_map.len() != corrupt_map.len() {
+        return Err(CorruptionError::LengthMismatch {
+            reference: ref_map.len() as u64,
+            corrupted: corrupt_map.len() as u64,
+        });
+    }
+
+    let corruptions = merge_chunks(mismatched_chunks_parallel(
+        &ref_map,
+        &corrupt_map,
+        chunk_size,
+        DEFAULT_WORK_BLOCK_SIZE,
+    ));
+
+    out.write_all(PATCH_MAGIC)?;
+    for corruption in &corruptions {
+        let start = corruption.offset as usize;
+        let end = start + corruption.length as usize;
+
+        out.write_all(&corruption.offset.to_le_bytes())?;
+        out.write_all(&corruption.length.to_le_bytes())?;
+        out.write_all(&ref_map[start..end])?;
+    }
+    out.flush()?;
+
+    Ok(corruptions)
 }
 
-pub fn find_corruptions_sequential(
-    reference_path: &str,
-    corrupted_path: &str,
-    chunk_size: usize,
-) -> Vec<Corruption> {
-    let mut ref_file = BufReader::new(File::open(reference_path).unwrap());
-    let mut corrupt_file = BufReader::new(File::open(corrupted_path).unwrap());
+/// Apply a patch written by [`write_patch`] to `target_path`, in place
+///
+/// Every region must fit in the target: a patch never grows the file. The
+/// whole patch is read and validated before the first write, so an invalid
+/// patch leaves the target untouched, at the cost of holding the patch in memory.
+pub fn apply_patch(target_path: &str, patch: &mut impl Read) -> Result<(), CorruptionError> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+    let mut magic = [0u8; 8];
+    patch.read_exact(&mut magic)?;
+    if &magic != PATCH_MAGIC {
+        return Err(invalid("not a blob patch").into());
+    }
+
+    let target_len = std::fs::metadata(target_path)?.len();
+
+    let mut header = [0u8; 16];
+    let mut records = Vec::new();
+
+    loop {
+        match read_chunk(patch, &mut header)? {
+            0 => break,
+            16 => {}
+            _ => return Err(invalid("truncated patch record").into()),
+        }
+
+        let offset = u64::from_le_bytes(header[..8].try_into().unwrap());
+        let length = u64::from_le_bytes(header[8..].try_into().unwrap());
+        if offset
+            .checked_add(length)
+            .is_none_or(|end| end > target_len)
+        {
+            return Err(invalid("patch region past the end of the target").into());
+        }
+
+        let mut bytes = vec![0u8; length as usize];
+        patch.read_exact(&mut bytes)?;
+        records.push((offset, bytes));
+    }
+
+    let mut target = OpenOptions::new().write(true).open(target_path)?;
+    for (offset, bytes) in records {
+        target.seek(SeekFrom::Start(offset))?;
+        target.write_all(&bytes)?;
+    }
+
+    target.flush()?;
+    Ok(())
+}
+
+/// Copy `corrupted_path` to `out_path`, overwriting known corruptions with `fill`
+///
+/// For salvaging a damaged file when no reference is available, e.g. with
+/// corruptions located by [`verify_against_crcs`]. Regions are cut at the end
+/// of the file, and overlapping regions are only filled once. Returns the
+/// number of bytes filled.
+pub fn salvage_copy(
+    corrupted_path: &str,
+    out_path: &str,
+    corruptions: &[Corruption],
+    fill: u8,
+) -> io::Result<u64> {
+    let file_len = std::fs::copy(corrupted_path, out_path)?;
+    let mut out = OpenOptions::new().write(true).open(out_path)?;
+
+    let mut filled = 0;
+    let mut bytes = Vec::new();
+    for corruption in merge_corruptions(corruptions, 0) {
+        let start = corruption.offset.min(file_len);
+        let end = (corruption.offset + corruption.length).min(file_len);
+        if start == end {
+            continue;
+        }
+
+        bytes.clear();
+        bytes.resize((end - start) as usize, fill);
+        out.seek(SeekFrom::Start(start))?;
+        out.write_all(&bytes)?;
+        filled += end - start;
+    }
+
+    out.flush()?;
+    Ok(filled)
+}
+
+/// Fill `buffer` from `reader`, only returning less than a full buffer at end of stream
+fn read_chunk(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(filled)
+}
+
+/// Merge sorted `(offset, length)` mismatched chunks into contiguous corruptions
+fn merge_chunks(chunks: impl IntoIterator<Item = (u64, u64)>) -> Vec<Corruption> {
+    let mut corruptions: Vec<Corruption> = Vec::new();
+
+    for (offset, length) in chunks {
+        match corruptions.last_mut() {
+            // Extend the previous corruption
+            Some(last) if last.offset + last.length == offset => last.length += length,
+            _ => corruptions.push(Corruption { offset, length }),
+        }
+    }
+
+    corruptions
+}
+
+/// Find the index of the first byte where `a` and `b` differ, comparing `LANES` bytes at a time
+///
+/// Only the lane group that fails the comparison is inspected further, using the
+/// mask's `first_set()` to locate the differing byte. Bytes that don't fill a whole
+/// lane group are compared with a single partial load, so there is no scalar loop.
+/// Returns `None` if both slices are equal.
+pub fn first_diff_simd<const LANES: usize>(a: &[u8], b: &[u8]) -> Option<usize> {
+    assert_eq!(a.len(), b.len(), "Slices must have the same length");
+
+    let a_chunks = a.chunks_exact(LANES);
+    let b_chunks = b.chunks_exact(LANES);
+    let tail_start = a.len() - a_chunks.remainder().len();
+
+    for (i, (a_chunk, b_chunk)) in a_chunks.zip(b_chunks).enumerate() {
+        let a_vec = Simd::<u8, LANES>::from_slice(a_chunk);
+        let b_vec = Simd::<u8, LANES>::from_slice(b_chunk);
+
+        if let Some(lane) = a_vec.simd_ne(b_vec).first_set() {
+            return Some(i * LANES + lane);
+        }
+    }
+
+    // Handle remaining bytes: lanes past the end of the slices are loaded as zeros
+    // and masked out of the comparison
+    let a_tail = &a[tail_start..];
+    let b_tail = &b[tail_start..];
+    if a_tail.is_empty() {
+        return None;
+    }
+
+    let a_vec = Simd::<u8, LANES>::load_or_default(a_tail);
+    let b_vec = Simd::<u8, LANES>::load_or_default(b_tail);
+    let in_bounds = Mask::<i8, LANES>::from_bitmask((1u64 << a_tail.len()) - 1);
+
+    (a_vec.simd_ne(b_vec) & in_bounds)
+        .first_set()
+        .map(|lane| tail_start + lane)
+}
+
+/// Compare two chunks `LANES` bytes at a time
+pub fn chunks_equal_simd<const LANES: usize>(a: &[u8], b: &[u8]) -> bool {
+    first_diff_simd::<LANES>(a, b).is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A reference/corrupted file pair in the temp dir, removed on drop
+    struct BlobPair {
+        reference: PathBuf,
+        corrupted: PathBuf,
+    }
+
+    impl BlobPair {
+        fn new(name: &str, reference: &[u8], corrupted: &[u8]) -> Self {
+            let dir = std::env::temp_dir();
+            let pair = BlobPair {
+                reference: dir.join(format!("eurorust_{name}_reference.bin")),
+                corrupted: dir.join(format!("eurorust_{name}_corrupted.bin")),
+            };
+
+            std::fs::write(&pair.reference, reference).unwrap();
+            std::fs::write(&pair.corrupted, corrupted).unwrap();
+            pair
+        }
+
+        fn reference(&self) -> &str {
+            self.reference.to_str().unwrap()
+        }
+
+        fn corrupted(&self) -> &str {
+            self.corrupted.to_str().unwrap()
+        }
+    }
+
+    impl Drop for BlobPair {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.reference);
+            let _ = std::fs::remove_file(&self.corrupted);
+        }
+    }
+
+    /// 10,000 bytes (the last 1 KB chunk is partial) with corruptions in
+    /// chunk 1, chunks 3-4 and the final partial chunk
+    fn sample_blobs() -> (Vec<u8>, Vec<u8>) {
+        let reference: Vec<u8> = (0..10_000).map(|i| (i % 251) as u8).collect();
+        let mut corrupted = reference.clone();
+        for offset in [1500, 3100, 4500, 9990] {
+            corrupted[offset] ^= 0xFF;
+        }
+        (reference, corrupted)
+    }
+
+    fn sample_corruptions() -> Vec<Corruption> {
+        vec![
+            Corruption {
+                offset: 1024,
+                length: 1024,
+            },
+            Corruption {
+                offset: 3072,
+                length: 2048,
+            },
+            Corruption {
+                offset: 9216,
+                length: 784,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_first_diff_simd_equal() {
+        let a: Vec<u8> = (0..100).collect();
+
+        assert_eq!(first_diff_simd::<16>(&a, &a), None);
+        assert!(chunks_equal_simd::<16>(&a, &a));
+        assert!(chunks_equal_simd::<16>(&[], &[]));
+    }
+
+    #[test]
+    fn test_first_diff_simd_lane_boundaries() {
+        let a: Vec<u8> = (0..100).collect();
+
+        // First and last byte of the first lane group, first byte of the next one
+        for diff_at in [0, 15, 16, 31, 32, 95] {
+            let mut b = a.clone();
+            b[diff_at] ^= 0xFF;
+
+            assert_eq!(first_diff_simd::<16>(&a, &b), Some(diff_at));
+            assert!(!chunks_equal_simd::<16>(&a, &b));
+        }
+    }
+
+    #[test]
+    fn test_first_diff_simd_reports_first_of_many() {
+        let a: Vec<u8> = (0..100).collect();
+        let mut b = a.clone();
+        b[20] ^= 0x01;
+        b[21] ^= 0x01;
+        b[70] ^= 0x01;
+
+        assert_eq!(first_diff_simd::<16>(&a, &b), Some(20));
+        assert_eq!(first_diff_simd::<64>(&a, &b), Some(20));
+    }
+
+    #[test]
+    fn test_chunks_equal_simd_non_multiple_lengths() {
+        for len in [1, 7, 15, 17, 63, 65, 127] {
+            let a: Vec<u8> = (0..len as u8).collect();
+            assert!(chunks_equal_simd::<16>(&a, &a), "Length {len}");
+            assert!(chunks_equal_simd::<64>(&a, &a), "Length {len}");
+
+            // Every single-byte difference is found, wherever it sits
+            for diff_at in 0..len {
+                let mut b = a.clone();
+                b[diff_at] ^= 0x01;
+
+                assert_eq!(first_diff_simd::<16>(&a, &b), Some(diff_at));
+                assert_eq!(first_diff_simd::<64>(&a, &b), Some(diff_at));
+            }
+        }
+    }
+
+    #[test]
+    fn test_lane_widths_agree() {
+        let a: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+
+        for diff_at in [None, Some(0), Some(7), Some(31), Some(500), Some(999)] {
+            let mut b = a.clone();
+            if let Some(index) = diff_at {
+                b[index] ^= 0x80;
+            }
+
+            assert_eq!(first_diff_simd::<8>(&a, &b), diff_at);
+            assert_eq!(first_diff_simd::<16>(&a, &b), diff_at);
+            assert_eq!(first_diff_simd::<32>(&a, &b), diff_at);
+            assert_eq!(first_diff_simd::<64>(&a, &b), diff_at);
+            for equal in [
+                chunks_equal_simd::<8>(&a, &b),
+                chunks_equal_simd::<16>(&a, &b),
+                chunks_equal_simd::<32>(&a, &b),
+                chunks_equal_simd::<64>(&a, &b),
+            ] {
+                assert_eq!(equal, diff_at.is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn test_first_diff_simd_masked_tail() {
+        // 100 bytes = 6 full lane groups of 16 + 4 tail bytes, loaded as one masked group
+        let a: Vec<u8> = (0..100).collect();
+        assert_eq!(first_diff_simd::<16>(&a, &a.clone()), None);
+
+        for diff_at in [96, 99] {
+            let mut b = a.clone();
+            b[diff_at] ^= 0xFF;
+
+            assert_eq!(first_diff_simd::<16>(&a, &b), Some(diff_at));
+            assert!(!chunks_equal_simd::<16>(&a, &b));
+        }
+    }
+
+    #[test]
+    fn test_find_corruptions_detects_single_bit_flip() {
+        let reference: Vec<u8> = (0..8192).map(|i| (i % 256) as u8).collect();
+        let mut corrupted = reference.clone();
+        corrupted[5000] ^= 0x01;
+        let blobs = BlobPair::new("single_bit", &reference, &corrupted);
+
+        let corruptions = find_corruptions_sequential(blobs.reference(), blobs.corrupted(), 1024);
+
+        assert_eq!(
+            corruptions,
+            vec![Corruption {
+                offset: 4096,
+                length: 1024
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_corruptions_parallel_matches_sequential() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("parallel", &reference, &corrupted);
+
+        let sequential = find_corruptions_sequential(blobs.reference(), blobs.corrupted(), 1024);
+        assert_eq!(sequential, sample_corruptions());
+        assert_eq!(
+            find_corruptions_sequential_mmap(blobs.reference(), blobs.corrupted(), 1024),
+            sequential
+        );
+
+        // One chunk per task, several chunks per task, a non-multiple of the
+        // chunk size, and a single block for the whole file
+        for work_block_size in [1024, 4096, 3000, 1 << 20] {
+            let parallel = find_corruptions_parallel(
+                blobs.reference(),
+                blobs.corrupted(),
+                1024,
+                work_block_size,
+            );
+            assert_eq!(parallel, sequential, "Work block size {work_block_size}");
+        }
+    }
 
-    let mut ref_buffer = vec![0u8; chunk_size];
-    let mut corrupt_buffer = vec![0u8; chunk_size];
+    #[test]
+    fn test_find_corruptions_simd_matches_sequential() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("simd", &reference, &corrupted);
 
-    let mut corruptions: Vec<Corruption> = Vec::new();
-    let mut offset = 0u64;
+        let corruptions = find_corruptions_simd(blobs.reference(), blobs.corrupted(), 1024);
+        assert_eq!(corruptions, sample_corruptions());
 
-    loop {
-        let n = ref_file.read(&mut ref_buffer).unwrap();
-        if n == 0 {
-            break;
+        // Super-chunks smaller than the file: matching ones are skipped, and
+        // 3000 is rounded up to 3 chunks
+        for super_chunk_size in [1024, 2048, 3000, 8192] {
+            let chunks = mismatched_chunks_simd(&reference, &corrupted, 1024, super_chunk_size);
+            let parallel_chunks =
+                mismatched_chunks_simd_parallel(&reference, &corrupted, 1024, super_chunk_size);
+            assert_eq!(
+                chunks, parallel_chunks,
+                "Super-chunk size {super_chunk_size}"
+            );
+            assert_eq!(
+                merge_chunks(chunks),
+                sample_corruptions(),
+                "Super-chunk size {super_chunk_size}"
+            );
         }
+    }
+
+    #[test]
+    fn test_find_corruptions_strategies_agree() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("strategies", &reference, &corrupted);
+        let strategies = [
+            ScanStrategy::Sequential,
+            ScanStrategy::Parallel,
+            ScanStrategy::Simd,
+            ScanStrategy::SimdParallel,
+        ];
 
-        corrupt_file.read_exact(&mut corrupt_buffer[..n]).unwrap();
+        // 1000 and 3000 leave a partial final chunk
+        for chunk_size in [1000, 1024, 3000] {
+            let expected =
+                find_corruptions_sequential_mmap(blobs.reference(), blobs.corrupted(), chunk_size);
 
-        // Compare byte by byte and track consecutive corrupted chunks
-        if ref_buffer[..n] != corrupt_buffer[..n] {
-            // Check if this continues the previous corruption
-            if let Some(last) = corruptions.last_mut() {
-                if last.offset + last.length == offset {
-                    // Extend the previous corruption
-                    last.length += n as u64;
-                } else {
-                    // New corruption
-                    corruptions.push(Corruption {
-                        offset,
-                        length: n as u64,
-                    });
-                }
-            } else {
-                // First corruption
-                corruptions.push(Corruption {
-                    offset,
-                    length: n as u64,
-                });
+            for strategy in strategies {
+                assert_eq!(
+                    find_corruptions(blobs.reference(), blobs.corrupted(), chunk_size, strategy),
+                    expected,
+                    "{strategy:?} with chunk size {chunk_size}"
+                );
             }
         }
+        assert_eq!(
+            find_corruptions(
+                blobs.reference(),
+                blobs.corrupted(),
+                1024,
+                ScanStrategy::Simd
+            ),
+            sample_corruptions()
+        );
+    }
+
+    #[test]
+    fn test_partial_final_chunk_agrees_across_scanners() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("partial_final_chunk", &reference, &corrupted);
+
+        // 3000 doesn't divide the file length (10,000) nor the BufReader
+        // capacity, the final chunk is 1000 bytes long
+        let expected = vec![
+            Corruption {
+                offset: 0,
+                length: 6000,
+            },
+            Corruption {
+                offset: 9000,
+                length: 1000,
+            },
+        ];
 
-        offset += n as u64;
+        let (reference_path, corrupted_path) = (blobs.reference(), blobs.corrupted());
+        assert_eq!(
+            find_corruptions_sequential(reference_path, corrupted_path, 3000),
+            expected
+        );
+        assert_eq!(
+            find_corruptions_profiled(reference_path, corrupted_path, 3000).0,
+            expected
+        );
+        assert_eq!(
+            find_corruptions_parallel(reference_path, corrupted_path, 3000, 4096),
+            expected
+        );
+        assert_eq!(
+            find_corruptions_simd(reference_path, corrupted_path, 3000),
+            expected
+        );
+        assert_eq!(
+            find_corruptions_simd_parallel(reference_path, corrupted_path, 3000),
+            expected
+        );
     }
 
-    corruptions
-}
+    #[test]
+    fn test_find_corruptions_profiled() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("profiled", &reference, &corrupted);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let (corruptions, profile) =
+            find_corruptions_profiled(blobs.reference(), blobs.corrupted(), 1024);
+
+        assert_eq!(corruptions, sample_corruptions());
+        assert!(profile.mmap > Duration::ZERO);
+        assert!(profile.compare > Duration::ZERO);
+        assert_eq!(
+            profile.total(),
+            profile.mmap + profile.compare + profile.merge
+        );
+    }
+
+    #[test]
+    fn test_find_mismatched_chunks_consistent_with_corruptions() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("mismatched_chunks", &reference, &corrupted);
+
+        let chunks = find_mismatched_chunks(blobs.reference(), blobs.corrupted(), 1024);
+        let corruptions = find_corruptions_sequential(blobs.reference(), blobs.corrupted(), 1024);
+
+        // Flips at 1500, 3100, 4500 and 9990: 3072 and 4096 are not merged
+        assert_eq!(chunks, vec![1024, 3072, 4096, 9216]);
+
+        // Every chunk falls in a corruption, and every corruption is covered by chunks
+        for offset in &chunks {
+            assert!(
+                corruptions
+                    .iter()
+                    .any(|c| c.offset <= *offset && *offset < c.offset + c.length),
+                "Chunk {offset} is outside every corruption"
+            );
+        }
+        for corruption in &corruptions {
+            let covering = chunks
+                .iter()
+                .filter(|&&offset| {
+                    corruption.offset <= offset && offset < corruption.offset + corruption.length
+                })
+                .count() as u64;
+            assert_eq!(covering, corruption.length.div_ceil(1024));
+        }
+    }
+
+    #[test]
+    fn test_longest_matching_run() {
+        let reference: Vec<u8> = (0..10_000).map(|i| (i % 251) as u8).collect();
+        let mut corrupted = reference.clone();
+        // Chunk 3 is corrupted: chunks 0-2 match on the left, 4-9 on the right
+        corrupted[3500] ^= 0xFF;
+        let blobs = BlobPair::new("longest_run", &reference, &corrupted);
+
+        let run = longest_matching_run(blobs.reference(), blobs.corrupted(), 1024);
+
+        // The last chunk is only 784 bytes long
+        assert_eq!(run, (4096, 10_000 - 4096));
+
+        // The sample blobs have 4 clean chunks between 5120 and 9216
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("longest_run_sample", &reference, &corrupted);
+        assert_eq!(
+            longest_matching_run(blobs.reference(), blobs.corrupted(), 1024),
+            (5120, 4096)
+        );
+    }
+
+    #[test]
+    fn test_rescan_corruptions() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("rescan", &reference, &corrupted);
+
+        // The flip at 1500 is outside every suspect region, the ones at 3100 and
+        // 4500 are covered by unaligned, overlapping regions
+        let suspect_regions = [
+            Corruption {
+                offset: 3000,
+                length: 200,
+            },
+            Corruption {
+                offset: 4100,
+                length: 2000,
+            },
+            Corruption {
+                offset: 9500,
+                length: 100,
+            },
+        ];
+        let corruptions =
+            rescan_corruptions(blobs.reference(), blobs.corrupted(), 1024, &suspect_regions);
+
+        assert_eq!(
+            corruptions,
+            vec![
+                Corruption {
+                    offset: 3072,
+                    length: 2048
+                },
+                Corruption {
+                    offset: 9216,
+                    length: 784
+                },
+            ]
+        );
+
+        // Suspecting the whole file is the same as a full scan
+        let everything = [Corruption {
+            offset: 0,
+            length: 10_000,
+        }];
+        assert_eq!(
+            rescan_corruptions(blobs.reference(), blobs.corrupted(), 1024, &everything),
+            sample_corruptions()
+        );
+    }
+
+    #[test]
+    fn test_find_corruptions_batch() {
+        let (reference, corrupted) = sample_blobs();
+        let corrupt_blobs = BlobPair::new("batch_corrupt", &reference, &corrupted);
+        let clean_blobs = BlobPair::new("batch_clean", &reference, &reference);
+
+        let results = find_corruptions_batch(
+            corrupt_blobs.reference(),
+            &[clean_blobs.corrupted(), corrupt_blobs.corrupted()],
+            1024,
+        );
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, clean_blobs.corrupted());
+        assert!(results[0].1.is_empty());
+        assert_eq!(results[1].0, corrupt_blobs.corrupted());
+        assert_eq!(results[1].1, sample_corruptions());
+    }
+
+    #[test]
+    fn test_corruption_heatmap() {
+        let (reference, mut corrupted) = sample_blobs();
+        // Rewrite the whole 6th chunk
+        for byte in &mut corrupted[5120..6144] {
+            *byte = !*byte;
+        }
+        let blobs = BlobPair::new("heatmap", &reference, &corrupted);
+
+        // 10 chunks (the last one partial) on rows of 4
+        let heatmap = corruption_heatmap(blobs.reference(), blobs.corrupted(), 1024, 4);
+        assert_eq!(heatmap.dimensions(), (4, 3));
+
+        let brightness = |chunk: u32| heatmap.get_pixel(chunk % 4, chunk / 4)[0];
+        for corrupted_chunk in [1, 3, 4, 5, 9] {
+            for clean_chunk in [0, 2, 6, 7, 8] {
+                assert!(brightness(corrupted_chunk) > brightness(clean_chunk));
+            }
+        }
+        assert_eq!(brightness(0), 0);
+        assert_eq!(brightness(5), 255);
+        // Past the end of the file
+        assert_eq!(brightness(10), 0);
+        assert_eq!(brightness(11), 0);
+    }
+
+    #[test]
+    fn test_find_corruptions_zstd() {
+        let (reference, corrupted) = sample_blobs();
+        let compressed = BlobPair::new(
+            "zstd",
+            &zstd::encode_all(reference.as_slice(), 3).unwrap(),
+            &zstd::encode_all(corrupted.as_slice(), 3).unwrap(),
+        );
+        let uncompressed = BlobPair::new("zstd_plain", &reference, &corrupted);
+
+        let corruptions =
+            find_corruptions_zstd(compressed.reference(), compressed.corrupted(), 1024).unwrap();
+
+        assert_eq!(
+            corruptions,
+            find_corruptions_sequential(uncompressed.reference(), uncompressed.corrupted(), 1024)
+        );
+        assert_eq!(corruptions, sample_corruptions());
+    }
+
+    #[test]
+    fn test_compare_streams_length_mismatch() {
+        let (reference, corrupted) = sample_blobs();
+
+        let result = compare_streams(&reference[..], &corrupted[..9000], 1024);
+
+        assert!(matches!(
+            result,
+            Err(CorruptionError::LengthMismatch {
+                reference: 10_000,
+                corrupted: 9000
+            })
+        ));
+    }
+
+    #[test]
+    fn test_verify_stream() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("verify_stream", &reference, &corrupted);
+
+        let corruptions =
+            verify_stream(blobs.corrupted(), io::Cursor::new(&reference), 1024).unwrap();
+
+        assert_eq!(corruptions, sample_corruptions());
+    }
+
+    #[test]
+    fn test_verify_stream_length_mismatch() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("verify_stream_length", &reference, &corrupted);
+
+        let shorter = &reference[..9000];
+        let result = verify_stream(blobs.corrupted(), io::Cursor::new(shorter), 1024);
+        assert!(matches!(
+            result,
+            Err(CorruptionError::LengthMismatch {
+                reference: 9000,
+                corrupted: 10_000
+            })
+        ));
+
+        let longer = [reference.as_slice(), &[0u8; 5000]].concat();
+        let result = verify_stream(blobs.corrupted(), io::Cursor::new(longer), 1024);
+        assert!(matches!(
+            result,
+            Err(CorruptionError::LengthMismatch {
+                reference: 15_000,
+                corrupted: 10_000
+            })
+        ));
+    }
 
     #[test]
     fn test_find_corruptions_sequential() {
@@ -98,4 +1877,445 @@ mod tests {
         );
         assert_eq!(corruptions[49].length, 5120, "Last corruption length");
     }
+
+    #[test]
+    fn test_crc_manifest_round_trip() {
+        let (reference, _) = sample_blobs();
+        let manifest = CrcManifest::from_bytes(&reference, 1024);
+
+        assert_eq!(manifest.crcs.len(), 10);
+        assert_eq!(manifest.crcs[0], crc32fast::hash(&reference[..1024]));
+
+        let mut bytes = Vec::new();
+        manifest.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 24 + 10 * 4);
+        assert_eq!(CrcManifest::read_from(&bytes[..]).unwrap(), manifest);
+
+        // Truncated checksums and wrong magic
+        assert!(CrcManifest::read_from(&bytes[..bytes.len() - 4]).is_err());
+        assert!(CrcManifest::read_from(&b"NOTBLOBS"[..]).is_err());
+
+        // Extra checksums
+        let mut longer = bytes.clone();
+        longer.extend_from_slice(&[0; 4]);
+        assert!(CrcManifest::read_from(&longer[..]).is_err());
+    }
+
+    #[test]
+    fn test_crc_manifest_rejects_invalid_headers() {
+        let header = |chunk_size: u64, length: u64| {
+            let mut bytes = CrcManifest::MAGIC.to_vec();
+            bytes.extend_from_slice(&chunk_size.to_le_bytes());
+            bytes.extend_from_slice(&length.to_le_bytes());
+            bytes
+        };
+
+        // A chunk size of 0, and a checksum count whose size overflows
+        for bytes in [header(0, 1024), header(1, u64::MAX)] {
+            let err = CrcManifest::read_from(&bytes[..]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn test_verify_against_crcs() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("verify_crcs", &reference, &corrupted);
+        let manifest = CrcManifest::from_bytes(&reference, 1024);
+
+        let corruptions = verify_against_crcs(blobs.corrupted(), &manifest).unwrap();
+        assert_eq!(corruptions, sample_corruptions());
+        assert!(
+            verify_against_crcs(blobs.reference(), &manifest)
+                .unwrap()
+                .is_empty()
+        );
+
+        let short = CrcManifest::from_bytes(&reference[..5000], 1024);
+        assert!(matches!(
+            verify_against_crcs(blobs.corrupted(), &short),
+            Err(CorruptionError::LengthMismatch {
+                reference: 5000,
+                corrupted: 10_000
+            })
+        ));
+    }
+
+    #[test]
+    fn test_merge_corruptions_gap_threshold() {
+        let corruption = |offset, length| Corruption { offset, length };
+        // 100 byte gap between the two corruptions
+        let corruptions = [corruption(1000, 50), corruption(1150, 50)];
+
+        assert_eq!(
+            merge_corruptions(&corruptions, 101),
+            vec![corruption(1000, 200)]
+        );
+        assert_eq!(merge_corruptions(&corruptions, 100), corruptions.to_vec());
+        assert_eq!(merge_corruptions(&corruptions, 0), corruptions.to_vec());
+    }
+
+    #[test]
+    fn test_merge_corruptions_unsorted_and_overlapping() {
+        let corruption = |offset, length| Corruption { offset, length };
+        let corruptions = [
+            corruption(5000, 100),
+            corruption(0, 1024),
+            corruption(512, 1024),
+            corruption(1536, 10),
+        ];
+
+        assert_eq!(
+            merge_corruptions(&corruptions, 0),
+            vec![corruption(0, 1546), corruption(5000, 100)]
+        );
+        assert_eq!(
+            merge_corruptions(&sample_corruptions(), 1025),
+            vec![corruption(1024, 4096), corruption(9216, 784)]
+        );
+        assert!(merge_corruptions(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn test_patch_round_trip() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("patch", &reference, &corrupted);
+
+        let mut patch = Vec::new();
+        let patched = write_patch(blobs.reference(), blobs.corrupted(), 1024, &mut patch).unwrap();
+        assert_eq!(patched, sample_corruptions());
+        // Magic, then a 16 byte header per region and the 1024 + 2048 + 784 bytes
+        assert_eq!(patch.len(), 8 + 3 * 16 + 3856);
+
+        apply_patch(blobs.corrupted(), &mut &patch[..]).unwrap();
+        assert_eq!(std::fs::read(blobs.corrupted()).unwrap(), reference);
+
+        // Nothing left to patch
+        let mut patch = Vec::new();
+        assert!(
+            write_patch(blobs.reference(), blobs.corrupted(), 1024, &mut patch)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(patch, PATCH_MAGIC);
+    }
+
+    #[test]
+    fn test_apply_patch_rejects_invalid_patches() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("invalid_patch", &reference, &corrupted);
+
+        let mut past_end = PATCH_MAGIC.to_vec();
+        past_end.extend_from_slice(&9_999u64.to_le_bytes());
+        past_end.extend_from_slice(&2u64.to_le_bytes());
+        past_end.extend_from_slice(&[0, 0]);
+
+        // A valid record followed by an invalid one: nothing is written
+        let mut valid_then_invalid = PATCH_MAGIC.to_vec();
+        valid_then_invalid.extend_from_slice(&1500u64.to_le_bytes());
+        valid_then_invalid.extend_from_slice(&1u64.to_le_bytes());
+        valid_then_invalid.push(reference[1500]);
+        valid_then_invalid.extend_from_slice(&past_end[8..]);
+
+        for patch in [
+            &b"NOTAPTCH"[..],
+            &past_end,
+            &past_end[..12],
+            &valid_then_invalid,
+            &valid_then_invalid[..valid_then_invalid.len() - 1],
+        ] {
+            assert!(apply_patch(blobs.corrupted(), &mut &patch[..]).is_err());
+        }
+        assert_eq!(std::fs::read(blobs.corrupted()).unwrap(), corrupted);
+    }
+
+    #[test]
+    fn test_assert_aligned() {
+        assert_eq!(assert_aligned(&sample_corruptions(), 1024), Ok(()));
+        assert_eq!(assert_aligned(&[], 1024), Ok(()));
+
+        let mut corruptions = sample_corruptions();
+        corruptions[1].offset += 1;
+        assert_eq!(
+            assert_aligned(&corruptions, 1024),
+            Err(AlignmentError::MisalignedOffset {
+                index: 1,
+                offset: 3073
+            })
+        );
+
+        // A partial chunk is only expected at the tail of the file
+        let mut corruptions = sample_corruptions();
+        corruptions[0].length = 1000;
+        assert_eq!(
+            assert_aligned(&corruptions, 1024),
+            Err(AlignmentError::MisalignedLength {
+                index: 0,
+                length: 1000
+            })
+        );
+    }
+
+    #[test]
+    fn test_corruption_ratio() {
+        let corruptions = sample_corruptions();
+
+        // 1024 + 2048 + 784 corrupted bytes out of 10,000
+        assert_eq!(total_corrupted_bytes(&corruptions), 3856);
+        assert_eq!(corruption_ratio(&corruptions, 10_000), 0.3856);
+
+        assert_eq!(total_corrupted_bytes(&[]), 0);
+        assert_eq!(corruption_ratio(&[], 10_000), 0.0);
+        assert_eq!(corruption_ratio(&corruptions, 0), 0.0);
+    }
+
+    #[test]
+    fn test_find_corruptions_resumable_in_two_halves() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("resumable", &reference, &corrupted);
+        let (reference_path, corrupted_path) = (blobs.reference(), blobs.corrupted());
+
+        // The first half stops in the middle of the 3072..5120 corruption
+        let (first, next) =
+            find_corruptions_resumable_bounded(reference_path, corrupted_path, 1024, 0, 4096);
+        assert_eq!(next, 4096);
+        let (second, next) = find_corruptions_resumable(reference_path, corrupted_path, 1024, next);
+        assert_eq!(next, 10_000);
+
+        let union = [first, second].concat();
+        assert_eq!(merge_corruptions(&union, 0), sample_corruptions());
+
+        // Resuming mid-chunk starts over at the chunk boundary
+        let (corruptions, next) =
+            find_corruptions_resumable(reference_path, corrupted_path, 1024, 1500);
+        assert_eq!(corruptions[0].offset, 1024);
+        assert_eq!(next, 10_000);
+        let (corruptions, next) =
+            find_corruptions_resumable_bounded(reference_path, corrupted_path, 1024, 1500, 1);
+        assert_eq!(
+            corruptions,
+            vec![Corruption {
+                offset: 1024,
+                length: 1024
+            }]
+        );
+        assert_eq!(next, 2048);
+
+        // Nothing left to scan
+        let (corruptions, next) =
+            find_corruptions_resumable(reference_path, corrupted_path, 1024, 10_000);
+        assert!(corruptions.is_empty());
+        assert_eq!(next, 10_000);
+    }
+
+    #[test]
+    fn test_files_identical() {
+        let (reference, _) = sample_blobs();
+        let blobs = BlobPair::new("identical", &reference, &reference);
+        assert!(files_identical(blobs.reference(), blobs.corrupted(), 1024).unwrap());
+
+        // A difference in the very first byte, and only in the last one
+        for (name, diff_at) in [("identical_early_diff", 0), ("identical_late_diff", 9999)] {
+            let mut different = reference.clone();
+            different[diff_at] ^= 0x01;
+            let pair = BlobPair::new(name, &reference, &different);
+
+            assert!(!files_identical(pair.reference(), pair.corrupted(), 1024).unwrap());
+        }
+
+        let short = BlobPair::new("identical_short", &reference, &reference[..5000]);
+        assert!(!files_identical(short.reference(), short.corrupted(), 1024).unwrap());
+
+        assert!(matches!(
+            files_identical(blobs.reference(), "does_not_exist.bin", 1024),
+            Err(CorruptionError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_corruption_byte_diffs() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("byte_diffs", &reference, &corrupted);
+        let [first, middle, last] = sample_corruptions().try_into().unwrap();
+        let byte = |offset: usize| (offset % 251) as u8;
+
+        assert_eq!(
+            corruption_byte_diffs(blobs.reference(), blobs.corrupted(), &first),
+            vec![(1500, byte(1500), byte(1500) ^ 0xFF)]
+        );
+        // Two flipped bytes in the 3072..5120 region
+        let diffs = corruption_byte_diffs(blobs.reference(), blobs.corrupted(), &middle);
+        assert_eq!(
+            diffs,
+            vec![
+                (3100, byte(3100), byte(3100) ^ 0xFF),
+                (4500, byte(4500), byte(4500) ^ 0xFF),
+            ]
+        );
+        // Cut at the end of the file
+        let past_end = Corruption {
+            offset: last.offset,
+            length: 4096,
+        };
+        assert_eq!(
+            corruption_byte_diffs(blobs.reference(), blobs.corrupted(), &past_end),
+            vec![(9990, byte(9990), byte(9990) ^ 0xFF)]
+        );
+    }
+
+    #[test]
+    fn test_find_corruptions_masked() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("masked", &reference, &corrupted);
+
+        assert_eq!(
+            find_corruptions_masked(blobs.reference(), blobs.corrupted(), 1024, &[]),
+            sample_corruptions()
+        );
+
+        // 1500 and 3100 are ignored, 4500 in the next chunk is still reported
+        let corruptions = find_corruptions_masked(
+            blobs.reference(),
+            blobs.corrupted(),
+            1024,
+            &[(1400, 1501), (3100, 3101)],
+        );
+        assert_eq!(
+            corruptions,
+            vec![
+                Corruption {
+                    offset: 4096,
+                    length: 1024
+                },
+                Corruption {
+                    offset: 9216,
+                    length: 784
+                },
+            ]
+        );
+
+        // The end of a range is excluded
+        let corruptions =
+            find_corruptions_masked(blobs.reference(), blobs.corrupted(), 1024, &[(1400, 1500)]);
+        assert_eq!(corruptions, sample_corruptions());
+    }
+
+    #[test]
+    fn test_count_corrupted_chunks() {
+        let (reference, corrupted) = sample_blobs();
+        let blobs = BlobPair::new("count_chunks", &reference, &corrupted);
+
+        for chunk_size in [100, 1000, 1024, 4096] {
+            let corruptions =
+                find_corruptions_sequential(blobs.reference(), blobs.corrupted(), chunk_size);
+            // Only the region at the end of the file can end with a partial chunk
+            let expected: u64 = corruptions
+                .iter()
+                .map(|corruption| corruption.length.div_ceil(chunk_size as u64))
+                .sum();
+
+            assert_eq!(
+                count_corrupted_chunks(blobs.reference(), blobs.corrupted(), chunk_size),
+                expected,
+                "chunk_size {chunk_size}"
+            );
+        }
+        // 1024 + 2048 + the 784 bytes tail
+        assert_eq!(
+            count_corrupted_chunks(blobs.reference(), blobs.corrupted(), 1024),
+            4
+        );
+
+        let identical = BlobPair::new("count_chunks_identical", &reference, &reference);
+        assert_eq!(
+            count_corrupted_chunks(identical.reference(), identical.corrupted(), 1024),
+            0
+        );
+    }
+
+    #[test]
+    fn test_salvage_copy() {
+        let (reference, corrupted) = sample_blobs();
+        // The reference slot is used as the output, and cleaned up the same way
+        let blobs = BlobPair::new("salvage", &[], &corrupted);
+
+        let mut corruptions = sample_corruptions();
+        // Overlaps the first region, and runs past the end of the file
+        corruptions.push(Corruption {
+            offset: 1536,
+            length: 1024,
+        });
+        corruptions.push(Corruption {
+            offset: 9216,
+            length: 4096,
+        });
+
+        let filled =
+            salvage_copy(blobs.corrupted(), blobs.reference(), &corruptions, 0x00).unwrap();
+
+        // 1024..2560, 3072..5120 and 9216..10,000
+        assert_eq!(filled, 1536 + 2048 + 784);
+        let salvaged = std::fs::read(blobs.reference()).unwrap();
+        assert_eq!(salvaged.len(), corrupted.len());
+        for (offset, &byte) in salvaged.iter().enumerate() {
+            let in_corruption =
+                (1024..2560).contains(&offset) || (3072..5120).contains(&offset) || offset >= 9216;
+            if in_corruption {
+                assert_eq!(byte, 0x00, "Offset {offset}");
+            } else {
+                assert_eq!(byte, corrupted[offset], "Offset {offset}");
+                assert_eq!(byte, reference[offset], "Offset {offset}");
+            }
+        }
+        // The source is left untouched
+        assert_eq!(std::fs::read(blobs.corrupted()).unwrap(), corrupted);
+    }
+
+    #[test]
+    fn test_find_corruptions_with_shift() {
+        let (reference, _) = sample_blobs();
+        let prefix = vec![0xAA; 512];
+        let inserted = [prefix.as_slice(), &reference].concat();
+        let blobs = BlobPair::new("shift_insert", &reference, &inserted);
+
+        // Byte-aligned, almost everything differs
+        assert_eq!(
+            find_corruptions_with_shift(blobs.reference(), blobs.corrupted(), 1024, 0),
+            vec![Corruption {
+                offset: 0,
+                length: 10_000
+            }]
+        );
+        assert_eq!(
+            find_corruptions_with_shift(blobs.reference(), blobs.corrupted(), 1024, 512),
+            vec![]
+        );
+
+        // Deletion: the first 512 bytes have no counterpart and aren't compared
+        let blobs = BlobPair::new("shift_delete", &reference, &reference[512..]);
+        assert_eq!(
+            find_corruptions_with_shift(blobs.reference(), blobs.corrupted(), 1024, -512),
+            vec![]
+        );
+
+        // A corruption after the shift is reported at its reference offset
+        let mut corrupted = inserted.clone();
+        corrupted[512 + 5000] ^= 0xFF;
+        let blobs = BlobPair::new("shift_corrupt", &reference, &corrupted);
+        assert_eq!(
+            find_corruptions_with_shift(blobs.reference(), blobs.corrupted(), 1024, 512),
+            vec![Corruption {
+                offset: 4096,
+                length: 1024
+            }]
+        );
+
+        // Shifted past the end of either file, nothing overlaps
+        for shift in [20_000, -20_000, i64::MAX, i64::MIN] {
+            assert!(
+                find_corruptions_with_shift(blobs.reference(), blobs.corrupted(), 1024, shift)
+                    .is_empty()
+            );
+        }
+    }
 }
@@ -0,0 +1,274 @@
+/// Separable box and (almost) Gaussian blurs
+///
+/// A box blur averages the `(2r + 1) x (2r + 1)` neighborhood of each pixel.
+/// It is separable: blurring the rows, then the columns, gives the same
+/// result as the 2D average for a fraction of the work. Each 1D pass keeps a
+/// running sum of the window, so its cost doesn't depend on the radius.
+///
+/// Passes always run down the columns, where a whole row of channel values
+/// is updated at once with SIMD. Rows are blurred by transposing the image,
+/// blurring its columns, and transposing it back. Values stay in `f32`
+/// between passes, and are only rounded once at the end.
+use std::simd::Simd;
+
+use image::RgbImage;
+
+use crate::helpers::build_rgb;
+
+const LANES: usize = 8;
+
+/// Box blur of radius `radius`, with the image edges extended
+///
+/// Pixels outside the image take the value of the closest edge pixel, so
+/// every output pixel is an average of `(2 * radius + 1)^2` values.
+pub fn box_blur(img: &RgbImage, radius: u32) -> RgbImage {
+    blur_with_radii(img, &[radius as usize])
+}
+
+/// Gaussian blur of standard deviation `sigma`, approximated by three box blurs
+///
+/// By the central limit theorem, repeated box blurs converge to a Gaussian,
+/// and three are already visually indistinguishable from one. A box of width
+/// `w` has a variance of `(w^2 - 1) / 12`, and variances add up across
+/// passes, so the widths must satisfy `sum((w_i^2 - 1) / 12) = sigma^2`.
+/// Box widths are odd, so there's no exact solution in general: the first
+/// `m` passes use the largest odd width `wl <= sqrt(12 * sigma^2 / 3 + 1)`,
+/// and the others `wl + 2`. Solving for `m` gives
+/// `m = (12 * sigma^2 - 3 * wl^2 - 12 * wl - 9) / (-4 * wl - 4)`, rounded.
+/// Each pass then has radius `(w - 1) / 2`. A `sigma` close to 0 gives radii
+/// of 0, which leave the image unchanged.
+pub fn gaussian_blur(img: &RgbImage, sigma: f32) -> RgbImage {
+    blur_with_radii(img, &gaussian_box_radii(sigma))
+}
+
+/// Radii of the three box blurs approximating a Gaussian, see [`gaussian_blur`]
+fn gaussian_box_radii(sigma: f32) -> [usize; 3] {
+    const PASSES: f32 = 3.0;
+    let variance = 12.0 * sigma.max(0.0).powi(2);
+
+    let ideal_width = (variance / PASSES + 1.0).sqrt();
+    let mut lower = ideal_width.floor() as i32;
+    if lower % 2 == 0 {
+        lower -= 1;
+    }
+    let lower = lower.max(1);
+    let upper = lower + 2;
+
+    let lower_f = lower as f32;
+    let ideal_count =
+        (variance - PASSES * lower_f * lower_f - 4.0 * PASSES * lower_f - 3.0 * PASSES)
+            / (-4.0 * lower_f - 4.0);
+    let count = ideal_count.round().clamp(0.0, PASSES) as usize;
+
+    std::array::from_fn(|pass| {
+        let width = if pass < count { lower } else { upper };
+        (width as usize - 1) / 2
+    })
+}
+
+/// Run one box blur per radius over the rows, then over the columns
+fn blur_with_radii(img: &RgbImage, radii: &[usize]) -> RgbImage {
+    let (width, height) = img.dimensions();
+    let (width, height) = (width as usize, height as usize);
+    if width == 0 || height == 0 || radii.iter().all(|&radius| radius == 0) {
+        return img.clone();
+    }
+
+    let values: Vec<f32> = img.as_raw().iter().map(|&value| value as f32).collect();
+
+    // Rows of the image are the columns of its transpose
+    let mut transposed = transpose(&values, width, height);
+    for &radius in radii {
+        transposed = blur_columns(&transposed, height * 3, width, radius);
+    }
+
+    let mut values = transpose(&transposed, height, width);
+    for &radius in radii {
+        values = blur_columns(&values, width * 3, height, radius);
+    }
+
+    let output = values
+        .iter()
+        .map(|&value| value.round().clamp(0.0, 255.0) as u8)
+        .collect();
+    build_rgb(width as u32, height as u32, output)
+        .expect("output buffer is sized from the input image")
+}
+
+/// Swap rows and columns of a `width x height` image of 3-channel pixels
+fn transpose(values: &[f32], width: usize, height: usize) -> Vec<f32> {
+    let mut transposed = vec![0.0; values.len()];
+
+    for y in 0..height {
+        for x in 0..width {
+            let from = (y * width + x) * 3;
+            let to = (x * height + y) * 3;
+            transposed[to..to + 3].copy_from_slice(&values[from..from + 3]);
+        }
+    }
+
+    transposed
+}
+
+/// 1D box blur down the columns, sliding a whole row of sums at a time
+///
+/// `sum` holds the window total of every column: each output row is `sum`
+/// scaled by the window size, then the row entering the window is added and
+/// the one leaving it subtracted. Rows past the edges are clamped.
+fn blur_columns(values: &[f32], row_len: usize, rows: usize, radius: usize) -> Vec<f32> {
+    if radius == 0 {
+        return values.to_vec();
+    }
+
+    let row = |y: usize| &values[y * row_len..(y + 1) * row_len];
+    let scale = 1.0 / (2 * radius + 1) as f32;
+
+    // Window of the first row: the top edge repeated radius + 1 times
+    let mut sum: Vec<f32> = row(0)
+        .iter()
+        .map(|&value| value * (radius + 1) as f32)
+        .collect();
+    for y in 1..=radius {
+        add_assign_diff(&mut sum, row(y.min(rows - 1)), None);
+    }
+
+    let mut output = vec![0.0; values.len()];
+    for (y, out_row) in output.chunks_exact_mut(row_len).enumerate() {
+        scale_into(out_row, &sum, scale);
+
+        let entering = row((y + radius + 1).min(rows - 1));
+        let leaving = row(y.saturating_sub(radius));
+        add_assign_diff(&mut sum, entering, Some(leaving));
+    }
+
+    output
+}
+
+/// `sum += add - sub`, `LANES` values at a time
+fn add_assign_diff(sum: &mut [f32], add: &[f32], sub: Option<&[f32]>) {
+    let sub = sub.unwrap_or(&[]);
+    let mut sum_chunks = sum.chunks_exact_mut(LANES);
+    let mut position = 0;
+
+    for sum_chunk in &mut sum_chunks {
+        let add_vec = Simd::<f32, LANES>::from_slice(&add[position..]);
+        let sub_vec = Simd::<f32, LANES>::load_or_default(sub.get(position..).unwrap_or(&[]));
+        (Simd::from_slice(sum_chunk) + add_vec - sub_vec).copy_to_slice(sum_chunk);
+        position += LANES;
+    }
+
+    for (i, value) in sum_chunks.into_remainder().iter_mut().enumerate() {
+        *value += add[position + i] - sub.get(position + i).copied().unwrap_or(0.0);
+    }
+}
+
+/// `out = sum * scale`, `LANES` values at a time
+fn scale_into(out: &mut [f32], sum: &[f32], scale: f32) {
+    let scale_vec = Simd::<f32, LANES>::splat(scale);
+    let mut out_chunks = out.chunks_exact_mut(LANES);
+    let sum_chunks = sum.chunks_exact(LANES);
+    let sum_tail = sum_chunks.remainder();
+
+    for (out_chunk, sum_chunk) in (&mut out_chunks).zip(sum_chunks) {
+        (Simd::from_slice(sum_chunk) * scale_vec).copy_to_slice(out_chunk);
+    }
+
+    for (out, &sum) in out_chunks.into_remainder().iter_mut().zip(sum_tail) {
+        *out = sum * scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::{checkerboard_image, gradient_image};
+    use image::Rgb;
+
+    /// Box blur computed directly from its definition, one pixel at a time
+    fn box_blur_reference(img: &RgbImage, radius: u32) -> RgbImage {
+        let (width, height) = img.dimensions();
+        let r = radius as i64;
+        let count = ((2 * r + 1) * (2 * r + 1)) as f32;
+
+        RgbImage::from_fn(width, height, |x, y| {
+            let mut sums = [0.0f32; 3];
+            for dy in -r..=r {
+                for dx in -r..=r {
+                    let sx = (x as i64 + dx).clamp(0, width as i64 - 1) as u32;
+                    let sy = (y as i64 + dy).clamp(0, height as i64 - 1) as u32;
+                    for (sum, &value) in sums.iter_mut().zip(&img.get_pixel(sx, sy).0) {
+                        *sum += value as f32;
+                    }
+                }
+            }
+            Rgb(sums.map(|sum| (sum / count).round() as u8))
+        })
+    }
+
+    #[test]
+    fn test_box_blur_matches_reference() {
+        // 13 pixels wide: rows of 39 values, not a multiple of the lanes
+        let img = RgbImage::from_fn(13, 7, |x, y| {
+            Rgb([(x * 19) as u8, (y * 37) as u8, ((x * y * 11) % 256) as u8])
+        });
+
+        for radius in [1, 2, 5, 20] {
+            let blurred = box_blur(&img, radius);
+            let expected = box_blur_reference(&img, radius);
+
+            for (actual, expected) in blurred.pixels().zip(expected.pixels()) {
+                for channel in 0..3 {
+                    assert!(
+                        actual[channel].abs_diff(expected[channel]) <= 1,
+                        "Radius {radius}: {actual:?} vs {expected:?}"
+                    );
+                }
+            }
+        }
+        assert_eq!(box_blur(&img, 0), img);
+    }
+
+    #[test]
+    fn test_gaussian_box_radii() {
+        assert_eq!(gaussian_box_radii(0.0), [0, 0, 0]);
+        assert_eq!(gaussian_box_radii(0.1), [0, 0, 0]);
+        // The variances of the three boxes add up close to sigma^2
+        for sigma in [1.0f32, 2.0, 3.5, 10.0] {
+            let variance: f32 = gaussian_box_radii(sigma)
+                .iter()
+                .map(|&radius| {
+                    let width = (2 * radius + 1) as f32;
+                    (width * width - 1.0) / 12.0
+                })
+                .sum();
+            assert!(
+                (variance - sigma * sigma).abs() <= sigma * sigma * 0.25 + 0.5,
+                "Sigma {sigma}: variance {variance}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_gaussian_blur() {
+        let img = gradient_image(40, 30);
+
+        // Sigma close to 0 is the identity
+        assert_eq!(gaussian_blur(&img, 0.0), img);
+        assert_eq!(gaussian_blur(&img, 0.1), img);
+
+        // A checkerboard averages out to gray, away from the edges where the
+        // repeated edge pixels bias the average
+        let black = Rgb([0, 0, 0]);
+        let white = Rgb([255, 255, 255]);
+        let blurred = gaussian_blur(&checkerboard_image(32, 32, black, white), 3.0);
+        for y in 10..22 {
+            for x in 10..22 {
+                let pixel = blurred.get_pixel(x, y);
+                assert!(pixel[0].abs_diff(128) <= 1, "({x}, {y}): {pixel:?}");
+            }
+        }
+        // A flat image stays flat
+        let flat = RgbImage::from_pixel(17, 9, Rgb([10, 100, 200]));
+        assert_eq!(gaussian_blur(&flat, 2.5), flat);
+    }
+}
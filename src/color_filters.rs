@@ -0,0 +1,367 @@
+/// Color adjustment filters
+///
+/// These filters change how colors look rather than where pixels are. They all
+/// work on `RgbImage` and, like the other filter modules, return a new image.
+use std::collections::HashMap;
+
+use image::{GrayImage, ImageBuffer, Rgb, RgbImage};
+
+use crate::helpers::build_rgb;
+
+/// Scale the saturation of every pixel by `factor`
+///
+/// Instead of a full RGB -> HSV -> RGB round trip, each channel is moved away
+/// from (or towards) the pixel's luminance: `gray + factor * (channel - gray)`.
+/// - `factor == 0.0` gives the grayscale image (R = G = B = luminance)
+/// - `factor == 1.0` leaves the image unchanged
+/// - `factor > 1.0` boosts colors
+pub fn adjust_saturation(img: &RgbImage, factor: f32) -> RgbImage {
+    let (width, height) = img.dimensions();
+    let mut output = ImageBuffer::new(width, height);
+
+    for (x, y, pixel) in img.enumerate_pixels() {
+        let r = pixel[0] as f32;
+        let g = pixel[1] as f32;
+        let b = pixel[2] as f32;
+
+        // Same luminosity weights as the grayscale conversion
+        let gray = r * 0.299 + g * 0.587 + b * 0.114;
+
+        let saturate =
+            |channel: f32| (gray + factor * (channel - gray)).round().clamp(0.0, 255.0) as u8;
+
+        output.put_pixel(x, y, Rgb([saturate(r), saturate(g), saturate(b)]));
+    }
+
+    output
+}
+
+/// Remap every channel through a tone curve defined by `control_points`
+///
+/// Each `(input, output)` point pins a value of the curve, see [`tone_curve_lut`].
+/// Linear brightness and contrast are special cases: two points on a line.
+pub fn apply_tone_curve(img: &RgbImage, control_points: &[(u8, u8)]) -> RgbImage {
+    let lut = tone_curve_lut(control_points);
+
+    let (width, height) = img.dimensions();
+    let output = img.as_raw().iter().map(|&c| lut[c as usize]).collect();
+
+    build_rgb(width, height, output).expect("output buffer is sized from the input image")
+}
+
+/// Build the 256-entry lookup table of a tone curve
+///
+/// Points are sorted by input, and values between two points are linearly
+/// interpolated (rounded to the nearest integer). Inputs below the first point
+/// or above the last one are clamped to that point's output.
+pub fn tone_curve_lut(control_points: &[(u8, u8)]) -> [u8; 256] {
+    assert!(
+        !control_points.is_empty(),
+        "A tone curve needs at least one control point"
+    );
+
+    let mut points = control_points.to_vec();
+    points.sort_by_key(|&(input, _)| input);
+
+    let (first, last) = (points[0], points[points.len() - 1]);
+
+    std::array::from_fn(|value| {
+        let value = value as u8;
+        if value <= first.0 {
+            return first.1;
+        }
+        if value >= last.0 {
+            return last.1;
+        }
+
+        // First point strictly after `value`, and the one just before it
+        let next = points.partition_point(|&(input, _)| input <= value);
+        let (x0, y0) = points[next - 1];
+        let (x1, y1) = points[next];
+
+        let t = (value - x0) as f32 / (x1 - x0) as f32;
+        (y0 as f32 + t * (y1 as f32 - y0 as f32)).round() as u8
+    })
+}
+
+/// Reduce the image to at most `n_colors` colors, see [`quantize_with_palette`]
+pub fn quantize(img: &RgbImage, n_colors: usize) -> RgbImage {
+    quantize_with_palette(img, n_colors).0
+}
+
+/// Reduce the image to at most `n_colors` colors, also returning the palette
+///
+/// The palette is picked with median cut: starting from a box holding every
+/// color of the histogram, the box with the widest channel range is split at
+/// the (pixel-weighted) median of that channel, until there are `n_colors`
+/// boxes or no box can be split anymore. Each box contributes the weighted
+/// average of its colors, and every pixel is mapped to its nearest palette entry.
+pub fn quantize_with_palette(img: &RgbImage, n_colors: usize) -> (RgbImage, Vec<Rgb<u8>>) {
+    assert!(n_colors > 0, "The palette needs at least one color");
+
+    let mut histogram: HashMap<[u8; 3], u64> = HashMap::new();
+    for pixel in img.pixels() {
+        *histogram.entry(pixel.0).or_default() += 1;
+    }
+    if histogram.is_empty() {
+        return (img.clone(), Vec::new());
+    }
+
+    // Sorted so the palette doesn't depend on the hash map's iteration order
+    let mut colors: Vec<_> = histogram.into_iter().collect();
+    colors.sort_unstable();
+
+    let mut boxes = vec![colors];
+    while boxes.len() < n_colors {
+        // The splittable box with the widest range on any channel
+        let Some((index, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(i, colors)| {
+                let (channel, range) = widest_channel(colors);
+                (range, i, channel)
+            })
+            .max()
+            .map(|(_, i, channel)| (i, channel))
+        else {
+            break;
+        };
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_by_key(|&(color, _)| color[channel]);
+
+        // Split where half the pixels are on each side, keeping both sides non-empty
+        let total: u64 = colors.iter().map(|&(_, count)| count).sum();
+        let mut below = 0;
+        let mut split = 1;
+        for (i, &(_, count)) in colors.iter().enumerate() {
+            below += count;
+            if below * 2 >= total {
+                split = i + 1;
+                break;
+            }
+        }
+        let split = split.min(colors.len() - 1);
+
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    let palette: Vec<Rgb<u8>> = boxes.iter().map(|colors| average_color(colors)).collect();
+
+    let mut nearest: HashMap<[u8; 3], Rgb<u8>> = HashMap::new();
+    let mut output = img.clone();
+    for pixel in output.pixels_mut() {
+        *pixel = *nearest
+            .entry(pixel.0)
+            .or_insert_with(|| nearest_color(&palette, pixel.0));
+    }
+
+    (output, palette)
+}
+
+/// False color: replace every gray value with its entry in `colormap`
+pub fn apply_colormap(img: &GrayImage, colormap: &[Rgb<u8>; 256]) -> RgbImage {
+    let (width, height) = img.dimensions();
+    let output = img
+        .as_raw()
+        .iter()
+        .flat_map(|&value| colormap[value as usize].0)
+        .collect();
+
+    build_rgb(width, height, output).expect("output buffer is sized from the input image")
+}
+
+/// The viridis colormap, from dark purple (0) to yellow (255)
+///
+/// Perceptually uniform and readable with color blindness. Built by linear
+/// interpolation between 9 evenly spaced colors of matplotlib's table.
+pub fn viridis_colormap() -> [Rgb<u8>; 256] {
+    const ANCHORS: [[u8; 3]; 9] = [
+        [68, 1, 84],
+        [72, 40, 120],
+        [62, 73, 137],
+        [49, 104, 142],
+        [38, 130, 142],
+        [31, 158, 137],
+        [53, 183, 121],
+        [110, 206, 88],
+        [253, 231, 37],
+    ];
+
+    std::array::from_fn(|i| {
+        let position = i as f32 / 255.0 * (ANCHORS.len() - 1) as f32;
+        let index = (position as usize).min(ANCHORS.len() - 2);
+        let fraction = position - index as f32;
+
+        let (low, high) = (ANCHORS[index], ANCHORS[index + 1]);
+        Rgb(std::array::from_fn(|channel| {
+            let (low, high) = (low[channel] as f32, high[channel] as f32);
+            (low + fraction * (high - low)).round() as u8
+        }))
+    })
+}
+
+/// The channel with the largest spread of values, and that spread
+fn widest_channel(colors: &[([u8; 3], u64)]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let values = colors.iter().map(|(color, _)| color[channel]);
+            let range = values.clone().max().unwrap() - values.min().unwrap();
+            (channel, range)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap()
+}
+
+/// Average of the colors, weighted by their pixel count
+fn average_color(colors: &[([u8; 3], u64)]) -> Rgb<u8> {
+    let total: u64 = colors.iter().map(|&(_, count)| count).sum();
+    let channel_average = |channel: usize| {
+        let sum: u64 = colors
+            .iter()
+            .map(|&(color, count)| color[channel] as u64 * count)
+            .sum();
+        ((sum + total / 2) / total) as u8
+    };
+
+    Rgb([channel_average(0), channel_average(1), channel_average(2)])
+}
+
+/// The palette entry closest to `color`, by squared Euclidean distance
+fn nearest_color(palette: &[Rgb<u8>], color: [u8; 3]) -> Rgb<u8> {
+    *palette
+        .iter()
+        .min_by_key(|entry| {
+            (0..3)
+                .map(|channel| {
+                    let diff = entry[channel] as i32 - color[channel] as i32;
+                    diff * diff
+                })
+                .sum::<i32>()
+        })
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_image() -> RgbImage {
+        ImageBuffer::from_fn(4, 4, |x, y| Rgb([(x * 60) as u8, (y * 60) as u8, 200]))
+    }
+
+    #[test]
+    fn test_saturation_zero_is_grayscale() {
+        let img = create_test_image();
+        let result = adjust_saturation(&img, 0.0);
+
+        for (input, output) in img.pixels().zip(result.pixels()) {
+            let luminance =
+                input[0] as f32 * 0.299 + input[1] as f32 * 0.587 + input[2] as f32 * 0.114;
+
+            assert_eq!(output[0], output[1]);
+            assert_eq!(output[1], output[2]);
+            assert_eq!(output[0], luminance.round() as u8);
+        }
+    }
+
+    #[test]
+    fn test_saturation_one_is_identity() {
+        let img = create_test_image();
+
+        assert_eq!(adjust_saturation(&img, 1.0), img);
+    }
+
+    #[test]
+    fn test_tone_curve_identity() {
+        let lut = tone_curve_lut(&[(0, 0), (255, 255)]);
+
+        for value in [0u8, 1, 64, 128, 200, 255] {
+            assert_eq!(lut[value as usize], value);
+        }
+
+        let img = create_test_image();
+        assert_eq!(apply_tone_curve(&img, &[(255, 255), (0, 0)]), img);
+    }
+
+    #[test]
+    fn test_tone_curve_s_curve() {
+        // Darken the shadows, brighten the highlights, unsorted on purpose
+        let lut = tone_curve_lut(&[(192, 224), (0, 0), (64, 32), (255, 255)]);
+
+        assert_eq!(lut[0], 0);
+        assert_eq!(lut[32], 16);
+        assert_eq!(lut[64], 32);
+        assert_eq!(lut[128], 128);
+        assert_eq!(lut[192], 224);
+        assert_eq!(lut[255], 255);
+    }
+
+    #[test]
+    fn test_tone_curve_clamps_outside_points() {
+        let lut = tone_curve_lut(&[(50, 10), (200, 240)]);
+
+        assert_eq!(lut[0], 10);
+        assert_eq!(lut[50], 10);
+        assert_eq!(lut[200], 240);
+        assert_eq!(lut[255], 240);
+    }
+
+    #[test]
+    fn test_quantize_two_colors_preserved() {
+        let img = ImageBuffer::from_fn(5, 4, |x, y| {
+            if (x + y) % 3 == 0 {
+                Rgb([200u8, 30, 10])
+            } else {
+                Rgb([20u8, 90, 240])
+            }
+        });
+
+        let (quantized, palette) = quantize_with_palette(&img, 2);
+
+        assert_eq!(quantized, img);
+        assert_eq!(palette.len(), 2);
+        assert!(palette.contains(&Rgb([200, 30, 10])));
+        assert!(palette.contains(&Rgb([20, 90, 240])));
+    }
+
+    #[test]
+    fn test_quantize_reduces_colors() {
+        let img = create_test_image();
+
+        let (quantized, palette) = quantize_with_palette(&img, 3);
+
+        assert_eq!(palette.len(), 3);
+        assert!(quantized.pixels().all(|pixel| palette.contains(pixel)));
+        // Asking for more colors than the image has keeps it unchanged
+        assert_eq!(quantize(&img, 100), img);
+    }
+
+    #[test]
+    fn test_saturation_boost_clamps() {
+        let img = ImageBuffer::from_pixel(1, 1, Rgb([250u8, 10, 10]));
+        let result = adjust_saturation(&img, 3.0);
+
+        assert_eq!(result.get_pixel(0, 0), &Rgb([255, 0, 0]));
+    }
+
+    #[test]
+    fn test_apply_colormap() {
+        let colormap = viridis_colormap();
+        assert_eq!(colormap[0], Rgb([68, 1, 84]));
+        assert_eq!(colormap[255], Rgb([253, 231, 37]));
+
+        let img = GrayImage::from_fn(3, 1, |x, _| image::Luma([[0, 128, 255][x as usize]]));
+        let colored = apply_colormap(&img, &colormap);
+
+        assert_eq!(colored.dimensions(), (3, 1));
+        assert_eq!(colored.get_pixel(0, 0), &colormap[0]);
+        assert_eq!(colored.get_pixel(1, 0), &colormap[128]);
+        assert_eq!(colored.get_pixel(2, 0), &colormap[255]);
+        // Mid-gray lands next to the teal middle anchor
+        assert_eq!(colormap[128], Rgb([38, 130, 142]));
+    }
+}
@@ -1,3 +1,17 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::hash::{BuildHasher, RandomState};
+use std::simd::Simd;
+use std::simd::cmp::SimdPartialEq;
+
+use aho_corasick::AhoCorasick;
+use memchr::{memchr, memmem, memrchr};
+use memmap2::Mmap;
+use rayon::prelude::*;
+use rustc_hash::FxBuildHasher;
+
+use crate::fasta::search_per_record;
+
 /// Naive approach: Read the entire file as a string and filter lines
 pub fn naive_dna_matcher(genome: &str, pattern: &str) -> Vec<String> {
     genome
@@ -8,10 +22,517 @@ pub fn naive_dna_matcher(genome: &str, pattern: &str) -> Vec<String> {
         .collect()
 }
 
+/// memchr backend: find every occurrence with `memmem`, then expand it to its line
+///
+/// Lines are deduplicated by their start offset, so a line containing the
+/// pattern several times is only returned once. Both LF and CRLF line endings
+/// are supported: the `\r` of a CRLF is not part of the returned line.
+pub fn memchr_search_bytes(genome: &[u8], pattern: &[u8]) -> Vec<Vec<u8>> {
+    to_owned_lines(memchr_search_refs(genome, pattern))
+}
+
+/// Same as [`memchr_search_bytes`], searching blocks of the genome in parallel
+///
+/// The genome is cut into a few blocks per rayon thread, each extended to the
+/// end of its last line, so no line (and no single-line match) straddles two
+/// blocks. Lines are then deduplicated within their block exactly as in the
+/// sequential version, and blocks are collected in order: the result doesn't
+/// depend on the number of threads.
+pub fn memchr_search_bytes_parallel(genome: &[u8], pattern: &[u8]) -> Vec<Vec<u8>> {
+    line_aligned_blocks(genome, rayon::current_num_threads() * 4)
+        .into_par_iter()
+        .flat_map_iter(|block| memchr_search_with_hasher::<FxBuildHasher>(block, pattern))
+        .map(<[u8]>::to_vec)
+        .collect()
+}
+
+/// Genome size, in bytes, from which [`search_auto`] switches to the parallel search
+///
+/// Below a few megabytes, splitting the genome and dispatching the blocks to
+/// rayon costs about as much as the search itself. 8 MB leaves the parallel
+/// version a clear win above it on a few cores.
+pub const PARALLEL_SEARCH_THRESHOLD: usize = 8 * 1024 * 1024;
+
+/// Search with [`memchr_search_bytes`] or [`memchr_search_bytes_parallel`],
+/// whichever is faster for the size of `genome`
+///
+/// Both return the same lines in the same order, so only the speed depends
+/// on the choice. See [`search_auto_with_threshold`] to tune the switch.
+pub fn search_auto(genome: &[u8], pattern: &[u8]) -> Vec<Vec<u8>> {
+    search_auto_with_threshold(genome, pattern, PARALLEL_SEARCH_THRESHOLD)
+}
+
+/// Same as [`search_auto`], searching in parallel from `threshold` bytes
+pub fn search_auto_with_threshold(genome: &[u8], pattern: &[u8], threshold: usize) -> Vec<Vec<u8>> {
+    if genome.len() < threshold {
+        memchr_search_bytes(genome, pattern)
+    } else {
+        memchr_search_bytes_parallel(genome, pattern)
+    }
+}
+
+/// Split `data` into about `count` blocks, each ending right after a newline
+/// (except the last one)
+fn line_aligned_blocks(data: &[u8], count: usize) -> Vec<&[u8]> {
+    let target = data.len().div_ceil(count.max(1)).max(1);
+    let mut blocks = Vec::with_capacity(count);
+
+    let mut start = 0;
+    while start < data.len() {
+        let end = (start + target).min(data.len());
+        let end = memchr(b'\n', &data[end..]).map_or(data.len(), |i| end + i + 1);
+        blocks.push(&data[start..end]);
+        start = end;
+    }
+
+    blocks
+}
+
+/// Search several FASTA files (e.g. genome shards) in parallel, one rayon task per file
+///
+/// Each file is memory-mapped and searched like [`memchr_search_bytes`].
+/// Results are returned in the order of `paths`, paired with the file path.
+pub fn search_files_parallel(paths: &[&str], pattern: &[u8]) -> Vec<(String, Vec<Vec<u8>>)> {
+    paths
+        .par_iter()
+        .map(|&path| {
+            let file = File::open(path).unwrap();
+            let genome = unsafe { Mmap::map(&file).unwrap() };
+
+            (path.to_string(), memchr_search_bytes(&genome, pattern))
+        })
+        .collect()
+}
+
+/// Same as [`memchr_search_bytes`], returning lines as strings
+///
+/// Bytes that aren't valid UTF-8 (some FASTA files have stray ones) are
+/// replaced with `U+FFFD` instead of panicking. For input that may be invalid,
+/// prefer the byte APIs such as [`memchr_search_bytes`], which return the
+/// lines exactly as they are in the file.
+pub fn memchr_search_lossy(genome: &[u8], pattern: &[u8]) -> Vec<String> {
+    memchr_search_refs(genome, pattern)
+        .into_iter()
+        .map(|line| String::from_utf8_lossy(line).into_owned())
+        .collect()
+}
+
+/// Same as [`memchr_search_bytes`], borrowing the matching lines from `genome`
+///
+/// Saves one allocation per match when the genome outlives the results.
+pub fn memchr_search_refs<'a>(genome: &'a [u8], pattern: &[u8]) -> Vec<&'a [u8]> {
+    memchr_search_with_hasher::<RandomState>(genome, pattern)
+}
+
+/// Same as [`memchr_search_bytes`], deduplicating lines with FxHash
+///
+/// The dedup set only ever holds line offsets, so the DoS resistance of the
+/// default SipHash buys nothing here, while its cost adds up on genomes with
+/// many matches.
+pub fn memchr_search_bytes_fast(genome: &[u8], pattern: &[u8]) -> Vec<Vec<u8>> {
+    to_owned_lines(memchr_search_with_hasher::<FxBuildHasher>(genome, pattern))
+}
+
+/// Headers of the records whose sequence contains `pattern`, even across line breaks
+///
+/// [`memchr_search_bytes`] searches line by line, so a pattern longer than a
+/// sequence line (usually 60 or 80 bases) or straddling a line break is never
+/// found. Here the sequence lines of each record are joined, without their
+/// LF or CRLF line endings, before searching. Headers are returned without
+/// the leading `>`, see [`search_per_record`] for the match offsets.
+pub fn search_across_lines<'a>(genome: &'a [u8], pattern: &[u8]) -> Vec<&'a [u8]> {
+    search_per_record(genome, pattern)
+        .into_iter()
+        .map(|(header, _)| header)
+        .collect()
+}
+
+fn memchr_search_with_hasher<'a, S: BuildHasher + Default>(
+    genome: &'a [u8],
+    pattern: &[u8],
+) -> Vec<&'a [u8]> {
+    let mut seen = HashSet::with_hasher(S::default());
+    let mut matches = Vec::new();
+
+    // Fast path: without the pattern's rarest byte anywhere, nothing can match
+    if let Some(rarest) = rarest_byte(pattern)
+        && memchr(rarest, genome).is_none()
+    {
+        return matches;
+    }
+
+    for pos in memmem::find_iter(genome, pattern) {
+        let line_start = memrchr(b'\n', &genome[..pos]).map_or(0, |i| i + 1);
+        let line_end = memchr(b'\n', &genome[pos..]).map_or(genome.len(), |i| pos + i);
+        let line = trim_cr(&genome[line_start..line_end]);
+
+        if line.starts_with(b">") {
+            continue; // Skip headers
+        }
+        if seen.insert(line_start) {
+            matches.push(line);
+        }
+    }
+
+    matches
+}
+
+/// The byte of `pattern` least likely to appear in a genome
+///
+/// Anything but an uppercase base (N, soft-masked bases, typos) is the
+/// rarest, then C and G, which are less frequent than A and T in most
+/// genomes. A `memchr` for it is the cheapest way to rule out a match.
+fn rarest_byte(pattern: &[u8]) -> Option<u8> {
+    pattern.iter().copied().min_by_key(|byte| match byte {
+        b'A' | b'T' => 2,
+        b'C' | b'G' => 1,
+        _ => 0,
+    })
+}
+
+/// Drop the `\r` left at the end of a line by CRLF (Windows) line endings
+fn trim_cr(line: &[u8]) -> &[u8] {
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+fn to_owned_lines(lines: Vec<&[u8]>) -> Vec<Vec<u8>> {
+    lines.into_iter().map(<[u8]>::to_vec).collect()
+}
+
+/// Number of occurrences of each pattern in the sequence lines, in the order of `patterns`
+///
+/// All patterns are matched together by one Aho-Corasick automaton, in a
+/// single pass over the genome instead of one per pattern. Overlapping
+/// occurrences all count, including those of different patterns at the same
+/// position. Matches don't span line breaks, and headers are skipped.
+pub fn multi_pattern_counts(genome: &[u8], patterns: &[&[u8]]) -> Vec<usize> {
+    let automaton = AhoCorasick::new(patterns).expect("Patterns should fit in an automaton");
+    let mut counts = vec![0; patterns.len()];
+
+    for line in genome.split(|&byte| byte == b'\n').map(trim_cr) {
+        if line.starts_with(b">") {
+            continue; // Skip headers
+        }
+        for found in automaton.find_overlapping_iter(line) {
+            counts[found.pattern().as_usize()] += 1;
+        }
+    }
+
+    counts
+}
+
+/// Search while excluding soft-masked (lowercase) regions
+///
+/// Lowercase bases mark masked repeats, so a match is only kept if every byte
+/// of the matched window is uppercase. The pattern itself is compared
+/// case-insensitively. Both rules together mean we only need an exact search
+/// for the uppercased pattern: any window it matches is uppercase by
+/// construction, and any window touching a lowercase byte can't match it.
+pub fn search_exclude_masked(genome: &[u8], pattern: &[u8]) -> Vec<Vec<u8>> {
+    memchr_search_bytes(genome, &pattern.to_ascii_uppercase())
+}
+
+/// Rabin-Karp: return the offset of every occurrence of `pattern` in the sequence lines
+///
+/// A polynomial hash of the current window is updated in O(1) as the window
+/// slides: the outgoing byte's contribution is subtracted and the incoming
+/// byte is appended. Hashes are computed modulo 2^64 via wrapping arithmetic,
+/// so equal hashes are only candidates and get verified byte by byte.
+pub fn rabin_karp_search(genome: &[u8], pattern: &[u8]) -> Vec<usize> {
+    const BASE: u64 = 257;
+
+    fn hash(bytes: &[u8]) -> u64 {
+        bytes.iter().fold(0, |hash, &byte| {
+            hash.wrapping_mul(BASE).wrapping_add(byte as u64)
+        })
+    }
+
+    let m = pattern.len();
+    let mut offsets = Vec::new();
+    if m == 0 {
+        return offsets;
+    }
+
+    let pattern_hash = hash(pattern);
+    // BASE^(m-1), the weight of the byte leaving the window
+    let high_weight = (1..m).fold(1u64, |weight, _| weight.wrapping_mul(BASE));
+
+    let mut line_start = 0;
+    for line in genome.split(|&byte| byte == b'\n') {
+        if !line.starts_with(b">") && line.len() >= m {
+            let mut window_hash = hash(&line[..m]);
+
+            for i in 0..=line.len() - m {
+                if i > 0 {
+                    window_hash = window_hash
+                        .wrapping_sub((line[i - 1] as u64).wrapping_mul(high_weight))
+                        .wrapping_mul(BASE)
+                        .wrapping_add(line[i + m - 1] as u64);
+                }
+                if window_hash == pattern_hash && &line[i..i + m] == pattern {
+                    offsets.push(line_start + i);
+                }
+            }
+        }
+
+        line_start += line.len() + 1;
+    }
+
+    offsets
+}
+
+/// Count the positions at which `a` and `b` differ, 16 bytes at a time
+///
+/// Each group of bytes is compared with `simd_ne`, and the resulting mask is
+/// turned into a bitmask whose set bits are counted. The last partial group is
+/// loaded zero-padded on both sides, so the padding never counts as a mismatch.
+pub fn hamming_distance_simd(a: &[u8], b: &[u8]) -> u32 {
+    const LANES: usize = 16;
+    assert_eq!(a.len(), b.len(), "Slices must have the same length");
+
+    a.chunks(LANES)
+        .zip(b.chunks(LANES))
+        .map(|(a_chunk, b_chunk)| {
+            let a_vec = Simd::<u8, LANES>::load_or_default(a_chunk);
+            let b_vec = Simd::<u8, LANES>::load_or_default(b_chunk);
+            a_vec.simd_ne(b_vec).to_bitmask().count_ones()
+        })
+        .sum()
+}
+
+/// Fuzzy search: return the sequence lines containing a window within
+/// `max_mismatches` substitutions of `pattern`
+///
+/// Each window is scored with `hamming_distance_simd`, and a line is accepted
+/// as soon as one window is close enough. An empty pattern has no window to
+/// score, and matches nothing.
+pub fn fuzzy_search_bytes(genome: &[u8], pattern: &[u8], max_mismatches: u32) -> Vec<Vec<u8>> {
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+
+    genome
+        .split(|&byte| byte == b'\n')
+        .map(trim_cr)
+        .filter(|line| !line.starts_with(b">")) // Skip headers
+        .filter(|line| {
+            line.windows(pattern.len())
+                .any(|window| hamming_distance_simd(window, pattern) <= max_mismatches)
+        })
+        .map(|line| line.to_vec())
+        .collect()
+}
+
+/// Motif search: score every window of the sequence lines against a position weight matrix
+///
+/// Row `i` of `pwm` holds the log-odds of A, C, G and T at position `i` of the
+/// motif, and a window's score is the sum of its bases' log-odds. Returns the
+/// genome offset and score of every window scoring above `threshold`. Bases
+/// are matched case-insensitively, and windows containing anything else (N,
+/// gaps) are skipped since they can't be scored.
+pub fn pwm_search(genome: &[u8], pwm: &[[f32; 4]], threshold: f32) -> Vec<(usize, f32)> {
+    fn column(byte: u8) -> Option<usize> {
+        match byte.to_ascii_uppercase() {
+            b'A' => Some(0),
+            b'C' => Some(1),
+            b'G' => Some(2),
+            b'T' => Some(3),
+            _ => None,
+        }
+    }
+
+    let m = pwm.len();
+    let mut hits = Vec::new();
+    if m == 0 {
+        return hits;
+    }
+
+    let mut line_start = 0;
+    for line in genome.split(|&byte| byte == b'\n') {
+        let sequence = trim_cr(line);
+        if !sequence.starts_with(b">") && sequence.len() >= m {
+            for (i, window) in sequence.windows(m).enumerate() {
+                let score = window
+                    .iter()
+                    .zip(pwm)
+                    .map(|(&byte, row)| column(byte).map(|base| row[base]))
+                    .sum::<Option<f32>>();
+
+                if let Some(score) = score
+                    && score > threshold
+                {
+                    hits.push((line_start + i, score));
+                }
+            }
+        }
+
+        line_start += line.len() + 1;
+    }
+
+    hits
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// A FASTA file in the temp dir, removed on drop even if the test panics
+    ///
+    /// The process id keeps concurrent test runs from sharing files.
+    struct TempFile(std::path::PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, content: &[u8]) -> Self {
+            let file_name = format!("eurorust_{}_{name}.fasta", std::process::id());
+            let path = std::env::temp_dir().join(file_name);
+            std::fs::write(&path, content).unwrap();
+            TempFile(path)
+        }
+
+        fn path(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn hamming_distance_scalar(a: &[u8], b: &[u8]) -> u32 {
+        a.iter().zip(b).filter(|(x, y)| x != y).count() as u32
+    }
+
+    #[test]
+    fn test_hamming_distance_simd_matches_scalar() {
+        let a = b"ACGTACGTACGTACGTACGTACGTACGTACGTACGTA";
+        let b = b"ACGAACGTTCGTACGTACGTACGGACGTACGTACGTC";
+
+        for len in [0, 1, 8, 16, 17, 32, a.len()] {
+            assert_eq!(
+                hamming_distance_simd(&a[..len], &b[..len]),
+                hamming_distance_scalar(&a[..len], &b[..len]),
+                "Length {len}"
+            );
+        }
+        assert_eq!(hamming_distance_simd(a, b), 4);
+    }
+
+    #[test]
+    fn test_memchr_search_bytes() {
+        let test_genome = b">seq1\nACGTACGT\n>AGTCCGTA\nAGTCCGTAAGTCCGTA\n>seq3\nGGGGGG";
+        let matches = memchr_search_bytes(test_genome, b"AGTCCGTA");
+
+        // The header is skipped and the line with two hits is returned once
+        assert_eq!(matches, vec![b"AGTCCGTAAGTCCGTA".to_vec()]);
+    }
+
+    #[test]
+    fn test_crlf_genome_matches_lf() {
+        let lf_genome = b">seq1 AGTC\nACGTAGTCAGTC\n>seq2\nAGTAGTCGGG\nTTAGTC\n>seq3\nAGTC";
+        let crlf_genome =
+            b">seq1 AGTC\r\nACGTAGTCAGTC\r\n>seq2\r\nAGTAGTCGGG\r\nTTAGTC\r\n>seq3\r\nAGTC";
+
+        let lf_matches = memchr_search_bytes(lf_genome, b"AGTC");
+        assert_eq!(lf_matches.len(), 4);
+        assert_eq!(memchr_search_bytes(crlf_genome, b"AGTC"), lf_matches);
+
+        assert_eq!(
+            fuzzy_search_bytes(crlf_genome, b"TTAGTC", 1),
+            fuzzy_search_bytes(lf_genome, b"TTAGTC", 1)
+        );
+    }
+
+    #[test]
+    fn test_memchr_search_lossy_invalid_utf8() {
+        let test_genome = b">seq1\nAGTC\xFFCGTA\n>seq2\nTTAGTCGG\n>seq3\nAGTC\xC3";
+        let matches = memchr_search_lossy(test_genome, b"AGTC");
+
+        assert_eq!(
+            matches,
+            vec!["AGTC\u{FFFD}CGTA", "TTAGTCGG", "AGTC\u{FFFD}"]
+        );
+    }
+
+    #[test]
+    fn test_memchr_search_refs_borrow_genome() {
+        let test_genome = b">seq1\nACGTACGT\n>AGTCCGTA\nAGTCCGTAAGTCCGTA\n>seq3\nTAGTCCGTAG";
+        let matches = memchr_search_refs(test_genome, b"AGTCCGTA");
+
+        assert_eq!(matches, vec![&b"AGTCCGTAAGTCCGTA"[..], b"TAGTCCGTAG"]);
+
+        // The slices point into the genome instead of copies of it
+        let genome_range = test_genome.as_ptr_range();
+        for line in &matches {
+            assert!(genome_range.contains(&line.as_ptr()));
+        }
+        assert_eq!(matches[0].as_ptr(), test_genome[25..].as_ptr());
+    }
+
+    #[test]
+    fn test_memchr_search_bytes_fast_matches_siphash() {
+        let test_genome =
+            b">seq1 AGTC\nACGTAGTCAGTC\n>seq2\nAGTAGTCGGGAGTC\nTTAGT\nCAA\n>seq3\nAGTC\nAGTC";
+
+        for pattern in [&b"AGTC"[..], b"GGG", b"TTAGT", b"missing"] {
+            assert_eq!(
+                memchr_search_bytes_fast(test_genome, pattern),
+                memchr_search_bytes(test_genome, pattern)
+            );
+        }
+    }
+
+    #[test]
+    fn test_search_exclude_masked() {
+        let test_genome = b">seq1\nTTAGTCCGTATT\n>seq2\nTTagtccgtaTT\n>seq3\nTTAGTCcgtaTT";
+
+        let matches = search_exclude_masked(test_genome, b"agtccgta");
+
+        // Fully and partially masked windows are both skipped
+        assert_eq!(matches, vec![b"TTAGTCCGTATT".to_vec()]);
+    }
+
+    #[test]
+    fn test_rabin_karp_matches_memmem() {
+        let test_genome =
+            b">seq1 AGTC\nACGTAGTCAGTC\n>seq2\nAGTAGTCGGGAGTC\nTTAGT\nCAA\n>seq3\nAGTC";
+        let pattern = b"AGTC";
+
+        let expected: Vec<usize> = memmem::find_iter(test_genome, pattern)
+            .filter(|&pos| {
+                let line_start = memrchr(b'\n', &test_genome[..pos]).map_or(0, |i| i + 1);
+                test_genome[line_start] != b'>'
+            })
+            .collect();
+
+        let offsets = rabin_karp_search(test_genome, pattern);
+        assert_eq!(offsets, expected);
+        assert_eq!(offsets.len(), 5);
+        for offset in offsets {
+            assert_eq!(&test_genome[offset..offset + pattern.len()], pattern);
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_search_bytes() {
+        let test_genome = b">seq1\nACGTACGT\n>seq2\nAGTCAGTAAA\n>AGTCCGTA\nGGGGGG";
+        let pattern = b"AGTCCGTA";
+
+        // One substitution away from the pattern
+        assert!(fuzzy_search_bytes(test_genome, pattern, 0).is_empty());
+        let matches = fuzzy_search_bytes(test_genome, pattern, 1);
+        assert_eq!(matches, vec![b"AGTCAGTAAA".to_vec()]);
+    }
+
+    #[test]
+    fn test_fuzzy_search_empty_pattern() {
+        let genome = b">seq1\nACGT\nTTTT\n";
+
+        assert!(fuzzy_search_bytes(genome, b"", 0).is_empty());
+        assert!(fuzzy_search_bytes(genome, b"", 3).is_empty());
+    }
+
     #[test]
     fn test_naive_matcher() {
         let test_genome = ">seq1\nACGTACGT\n>seq2\nAGTCCGTAAA\n>seq3\nGGGGGG";
@@ -44,4 +565,172 @@ mod tests {
             pattern
         );
     }
+
+    #[test]
+    fn test_memchr_search_absent_base() {
+        let genome = b">seq\nACGTACGT\nTTGACA\n";
+
+        // No N in the genome: the fast path returns before searching
+        assert_eq!(rarest_byte(b"ACNGT"), Some(b'N'));
+        assert!(memchr_search_bytes(genome, b"ACNGT").is_empty());
+        assert!(memchr_search_bytes_fast(genome, b"ACNGT").is_empty());
+
+        // The rarest byte is present, the full search still runs
+        assert_eq!(rarest_byte(b"TTGA"), Some(b'G'));
+        assert_eq!(
+            memchr_search_bytes(genome, b"TTGA"),
+            vec![b"TTGACA".to_vec()]
+        );
+        assert_eq!(rarest_byte(b""), None);
+    }
+
+    #[test]
+    fn test_search_files_parallel() {
+        let shards = [
+            TempFile::new("search_files_parallel_1", b">chr1\nACGTAGTCCGTA\nTTTT\n"),
+            TempFile::new(
+                "search_files_parallel_2",
+                b">chr2\nGGGG\nCCCC\n>chr3\nAGTCCGTAAGTCCGTA\n",
+            ),
+        ];
+        let paths: Vec<&str> = shards.iter().map(TempFile::path).collect();
+
+        let results = search_files_parallel(&paths, b"AGTCCGTA");
+
+        assert_eq!(
+            results,
+            vec![
+                (paths[0].to_string(), vec![b"ACGTAGTCCGTA".to_vec()]),
+                (paths[1].to_string(), vec![b"AGTCCGTAAGTCCGTA".to_vec()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pwm_search() {
+        // Favors TATA: +1 for the consensus base, -1 for any other
+        let consensus = b"TATA";
+        let pwm: Vec<[f32; 4]> = consensus
+            .iter()
+            .map(|&base| {
+                let mut row = [-1.0; 4];
+                row[b"ACGT".iter().position(|&b| b == base).unwrap()] = 1.0;
+                row
+            })
+            .collect();
+        let genome = b">TATA header\nGGTATAGG\r\n>seq2\nCCGCtataNTATC";
+
+        let hits = pwm_search(genome, &pwm, 1.5);
+
+        // Exact motifs score 4.0 in any case, TATC (one mismatch) scores 2.0
+        assert_eq!(hits, vec![(15, 4.0), (33, 4.0), (38, 2.0)]);
+        // A random window like GGTA (-1 -1 +1 +1) stays below the threshold
+        assert!(pwm_search(b"GGTA", &pwm, 1.5).is_empty());
+        assert!(pwm_search(genome, &[], 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_search_across_lines() {
+        // 80-base lines, as in most FASTA files
+        let line_1 = [b"ACGT".repeat(19), b"GATT".to_vec()].concat();
+        let line_2 = [b"ACAT".to_vec(), b"CCGG".repeat(19)].concat();
+        assert_eq!((line_1.len(), line_2.len()), (80, 80));
+        let genome = [
+            b">chr1 spanning\n".as_slice(),
+            &line_1,
+            b"\n",
+            &line_2,
+            b"\n>chr2\r\n",
+            &line_1,
+            b"\r\n",
+            &line_2,
+            b"\r\n>chr3\n",
+            &line_1,
+            b"\n",
+        ]
+        .concat();
+
+        // 90 bases straddling the line break: longer than any single line
+        let pattern = [&line_1[40..], &line_2[..50]].concat();
+        assert_eq!(pattern.len(), 90);
+        assert!(memchr_search_bytes(&genome, &pattern).is_empty());
+        assert_eq!(
+            search_across_lines(&genome, &pattern),
+            vec![b"chr1 spanning".as_slice(), b"chr2"]
+        );
+
+        // Just the junction
+        assert_eq!(
+            search_across_lines(&genome, b"GATTACAT"),
+            vec![b"chr1 spanning".as_slice(), b"chr2"]
+        );
+        assert!(search_across_lines(&genome, b"GATTACATT").is_empty());
+    }
+
+    #[test]
+    fn test_memchr_search_bytes_parallel_matches_sequential() {
+        let genome = b">seq1 AGTC\nACGTAGTCAGTC\r\n>seq2\nAGTAGTCGGG\nTTAGTC\n>seq3\nAGTC";
+        let expected = memchr_search_bytes(genome, b"AGTC");
+        assert_eq!(expected.len(), 4);
+
+        // From one block per line and less, to a single block for everything
+        for count in [1, 2, 3, 7, 64] {
+            let blocks = line_aligned_blocks(genome, count);
+            assert_eq!(blocks.concat(), genome, "{count} blocks");
+            for block in &blocks[..blocks.len() - 1] {
+                assert!(block.ends_with(b"\n"), "{count} blocks");
+            }
+        }
+        assert_eq!(memchr_search_bytes_parallel(genome, b"AGTC"), expected);
+        assert!(line_aligned_blocks(b"", 4).is_empty());
+    }
+
+    #[test]
+    fn test_multi_pattern_counts() {
+        let genome = b">AGTC in a header\nAGTCAGTC\r\nTTAGTCCGTA\n>seq2\nCCGTAGT\nCAAAA";
+        let patterns: [&[u8]; 3] = [b"AGTC", b"CCGTA", b"GATTACA"];
+
+        // AGTC twice on the first line and once on the second, but not split
+        // across lines; CCGTA overlaps an AGTC
+        assert_eq!(multi_pattern_counts(genome, &patterns), vec![3, 2, 0]);
+        for (pattern, count) in patterns.iter().zip(multi_pattern_counts(genome, &patterns)) {
+            assert_eq!(rabin_karp_search(genome, pattern).len(), count);
+        }
+
+        // Overlapping occurrences of the same pattern all count
+        assert_eq!(multi_pattern_counts(b"AAAAA", &[b"AA", b"AAA"]), vec![4, 3]);
+        assert!(multi_pattern_counts(genome, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_search_auto_matches_explicit_searches() {
+        let small = b">seq1 AGTC\nACGTAGTCAGTC\r\n>seq2\nAGTAGTCGGG\nTTAGTC\n>seq3\nAGTC";
+        let expected = memchr_search_bytes(small, b"AGTC");
+        assert_eq!(search_auto(small, b"AGTC"), expected);
+        // Forced to the parallel search, and to the sequential one
+        assert_eq!(search_auto_with_threshold(small, b"AGTC", 0), expected);
+        assert_eq!(
+            search_auto_with_threshold(small, b"AGTC", usize::MAX),
+            expected
+        );
+
+        // Past the default threshold: one match every 1000 lines
+        let line_count = PARALLEL_SEARCH_THRESHOLD / 60 + 1000;
+        let mut large = b">chr1 large\n".to_vec();
+        for i in 0..line_count {
+            let base = if i % 1000 == 0 {
+                b"GATTACA"
+            } else {
+                b"CCCCCCC"
+            };
+            large.extend_from_slice(&base.repeat(9)[..59]);
+            large.push(b'\n');
+        }
+        assert!(large.len() >= PARALLEL_SEARCH_THRESHOLD);
+
+        let expected = memchr_search_bytes_parallel(&large, b"GATTACA");
+        assert_eq!(expected.len(), line_count.div_ceil(1000));
+        assert_eq!(search_auto(&large, b"GATTACA"), expected);
+        assert_eq!(memchr_search_bytes(&large, b"GATTACA"), expected);
+    }
 }
@@ -0,0 +1,218 @@
+/// FASTA record parsing and comparison
+///
+/// A FASTA file is a list of records, each made of a `>header` line followed
+/// by any number of sequence lines. Records are borrowed straight from the
+/// input bytes, so iterating over a memory-mapped genome doesn't copy it.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hasher;
+
+use memchr::{memchr, memmem};
+
+use crate::nucleotide::can_contain;
+
+/// One record of a FASTA file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FastaRecord<'a> {
+    /// Header line without the leading `>` and the line ending
+    pub header: &'a [u8],
+    /// Raw sequence lines, line endings included
+    pub body: &'a [u8],
+}
+
+impl<'a> FastaRecord<'a> {
+    /// Non-empty sequence lines, without their line endings (LF or CRLF)
+    pub fn sequence_lines(&self) -> impl Iterator<Item = &'a [u8]> + 'a {
+        self.body
+            .split(|&byte| byte == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+            .filter(|line| !line.is_empty())
+    }
+
+    /// The whole sequence, with the lines joined together
+    pub fn sequence(&self) -> Vec<u8> {
+        self.sequence_lines().flatten().copied().collect()
+    }
+}
+
+/// Iterator over the records of a FASTA file
+///
+/// Anything before the first header is skipped.
+pub struct FastaRecords<'a> {
+    remaining: &'a [u8],
+}
+
+impl<'a> FastaRecords<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        let start = if data.starts_with(b">") {
+            0
+        } else {
+            memmem::find(data, b"\n>").map_or(data.len(), |pos| pos + 1)
+        };
+
+        FastaRecords {
+            remaining: &data[start..],
+        }
+    }
+}
+
+impl<'a> Iterator for FastaRecords<'a> {
+    type Item = FastaRecord<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Always positioned on a '>' or at the end of the input
+        let record = self.remaining.get(1..)?;
+
+        let header_end = memchr(b'\n', record).unwrap_or(record.len());
+        let header = &record[..header_end];
+        let header = header.strip_suffix(b"\r").unwrap_or(header);
+        let rest = record.get(header_end + 1..).unwrap_or_default();
+
+        let body_end = memmem::find(rest, b"\n>").map_or(rest.len(), |pos| pos + 1);
+        self.remaining = &rest[body_end..];
+
+        Some(FastaRecord {
+            header,
+            body: &rest[..body_end],
+        })
+    }
+}
+
+/// Offsets of `pattern` in the sequence of every record, skipping records without matches
+///
+/// Sequences are joined before searching, so a match may span a line break.
+/// Records that can't hold the pattern's bases (see [`can_contain`]) are
+/// skipped without joining their lines.
+pub fn search_per_record<'a>(data: &'a [u8], pattern: &[u8]) -> Vec<(&'a [u8], Vec<usize>)> {
+    let finder = memmem::Finder::new(pattern);
+
+    FastaRecords::new(data)
+        .filter(|record| can_contain(record.body, pattern))
+        .filter_map(|record| {
+            let offsets: Vec<usize> = finder.find_iter(&record.sequence()).collect();
+            (!offsets.is_empty()).then_some((record.header, offsets))
+        })
+        .collect()
+}
+
+/// A difference between two FASTA files, see [`diff_fasta`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordDiff {
+    /// The record is in the first file only
+    Missing { header: Vec<u8> },
+    /// The record is in the second file only
+    Added { header: Vec<u8> },
+    /// Both files have the record, but with different sequences
+    Changed { header: Vec<u8> },
+}
+
+/// Compare two FASTA files record by record, matching records by header
+///
+/// Sequences are compared by hash, ignoring how they are split into lines.
+/// Missing and changed records are reported in the order of `a`, followed by
+/// the added records in the order of `b`.
+pub fn diff_fasta(a: &[u8], b: &[u8]) -> Vec<RecordDiff> {
+    let b_hashes: HashMap<&[u8], u64> = FastaRecords::new(b)
+        .map(|record| (record.header, sequence_hash(&record)))
+        .collect();
+
+    let mut diffs = Vec::new();
+    let mut a_headers = HashSet::new();
+
+    for record in FastaRecords::new(a) {
+        a_headers.insert(record.header);
+
+        match b_hashes.get(record.header) {
+            None => diffs.push(RecordDiff::Missing {
+                header: record.header.to_vec(),
+            }),
+            Some(&hash) if hash != sequence_hash(&record) => diffs.push(RecordDiff::Changed {
+                header: record.header.to_vec(),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    for record in FastaRecords::new(b) {
+        if !a_headers.contains(record.header) {
+            diffs.push(RecordDiff::Added {
+                header: record.header.to_vec(),
+            });
+        }
+    }
+
+    diffs
+}
+
+fn sequence_hash(record: &FastaRecord) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for line in record.sequence_lines() {
+        hasher.write(line);
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fasta_records() {
+        let data = b">first\nACGT\nAC\n>second\nTTTT\n>empty\n";
+        let records: Vec<_> = FastaRecords::new(data).collect();
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].header, b"first");
+        assert_eq!(records[0].sequence(), b"ACGTAC");
+        assert_eq!(records[1].header, b"second");
+        assert_eq!(records[1].sequence(), b"TTTT");
+        assert_eq!(records[2].header, b"empty");
+        assert!(records[2].sequence().is_empty());
+    }
+
+    #[test]
+    fn test_fasta_records_no_trailing_newline() {
+        let data = b"ignored\n>only\nGATTACA";
+        let records: Vec<_> = FastaRecords::new(data).collect();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].header, b"only");
+        assert_eq!(records[0].sequence(), b"GATTACA");
+    }
+
+    #[test]
+    fn test_diff_fasta() {
+        let original = b">seq1\nACGT\nACGT\n>seq2\nGGGG\n>seq3\nTTTT\n";
+        // seq1 is split differently but unchanged, seq2 changed, seq3 removed, seq4 added
+        let reprocessed = b">seq1\nACGTACGT\n>seq2\nGGGA\n>seq4\nCCCC\n";
+
+        let diffs = diff_fasta(original, reprocessed);
+
+        assert_eq!(
+            diffs,
+            vec![
+                RecordDiff::Changed {
+                    header: b"seq2".to_vec()
+                },
+                RecordDiff::Missing {
+                    header: b"seq3".to_vec()
+                },
+                RecordDiff::Added {
+                    header: b"seq4".to_vec()
+                },
+            ]
+        );
+        assert!(diff_fasta(original, original).is_empty());
+    }
+
+    #[test]
+    fn test_search_per_record() {
+        // no_g lacks the G of the pattern and is skipped by the pre-filter
+        let data = b">no_g\nATCATCATCA\n>split\nTTGA\nTCAAGATC\n>no_match\nGGGAAATTTCCC\n";
+        assert!(!can_contain(b"ATCATCATCA", b"GATC"));
+
+        let results = search_per_record(data, b"GATC");
+
+        assert_eq!(results, vec![(&b"split"[..], vec![2, 8])]);
+    }
+}
@@ -1,21 +1,387 @@
-use image::{GrayImage, RgbImage};
+use std::fmt;
+
+use image::{GrayImage, ImageBuffer, Pixel, Rgb, RgbImage};
+use rayon::prelude::*;
+
+/// Errors surfaced by the image filters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterError {
+    /// The raw buffer length doesn't match `width * height * channels`
+    BufferSizeMismatch {
+        width: u32,
+        height: u32,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterError::BufferSizeMismatch {
+                width,
+                height,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "buffer of {actual} values doesn't fit a {width}x{height} image ({expected} values expected)"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+/// Wrap a raw buffer into an image, checking its length against the dimensions
+///
+/// The buffer must hold exactly `width * height` pixels: a shorter or longer
+/// one is a [`FilterError::BufferSizeMismatch`].
+pub(crate) fn build_image<P: Pixel>(
+    width: u32,
+    height: u32,
+    buf: Vec<P::Subpixel>,
+) -> Result<ImageBuffer<P, Vec<P::Subpixel>>, FilterError> {
+    let expected = (width as usize * height as usize).saturating_mul(P::CHANNEL_COUNT as usize);
+    let actual = buf.len();
+    let mismatch = || FilterError::BufferSizeMismatch {
+        width,
+        height,
+        expected,
+        actual,
+    };
+
+    // from_raw only rejects buffers that are too short
+    if actual != expected {
+        return Err(mismatch());
+    }
+    ImageBuffer::from_raw(width, height, buf).ok_or_else(mismatch)
+}
+
+/// Wrap a raw RGB buffer into an image, see [`build_image`]
+pub(crate) fn build_rgb(width: u32, height: u32, buf: Vec<u8>) -> Result<RgbImage, FilterError> {
+    build_image(width, height, buf)
+}
+
+/// Deterministic test image: red grows left to right, green top to bottom
+///
+/// Blue is the complement of red, so every channel covers its full range
+/// whatever the size. The top left pixel is `[0, 0, 255]`.
+pub fn gradient_image(width: u32, height: u32) -> RgbImage {
+    RgbImage::from_fn(width, height, |x, y| {
+        let red = (x * 256 / width) as u8;
+        let green = (y * 256 / height) as u8;
+        Rgb([red, green, 255 - red])
+    })
+}
+
+/// Deterministic test image alternating `a` and `b` pixel by pixel, starting with `a`
+pub fn checkerboard_image(width: u32, height: u32, a: Rgb<u8>, b: Rgb<u8>) -> RgbImage {
+    RgbImage::from_fn(width, height, |x, y| if (x + y) % 2 == 0 { a } else { b })
+}
 
 pub fn assert_eq_img(img_1: &RgbImage, img_2: &RgbImage) {
+    assert_similarity_at_least(img_1, img_2, 0.99);
+}
+
+/// Assert that the root-mean-squared similarity of two images is above `threshold`
+///
+/// Lossy approximations can use a lower bar than [`assert_eq_img`], while exact
+/// ports can require a score close to 1.
+pub fn assert_similarity_at_least(img_1: &RgbImage, img_2: &RgbImage, threshold: f64) {
     let result = image_compare::rgb_similarity_structure(
         &image_compare::Algorithm::RootMeanSquared,
-        &img_1,
-        &img_2,
+        img_1,
+        img_2,
     )
     .unwrap();
-    assert!(result.score > 0.99);
+    assert!(
+        result.score > threshold,
+        "Similarity {} is not above {threshold}",
+        result.score
+    );
+}
+
+/// Same as [`assert_eq_img`], computing the score with [`rgb_similarity_parallel`]
+pub fn assert_eq_img_parallel(img_1: &RgbImage, img_2: &RgbImage) {
+    assert!(rgb_similarity_parallel(img_1, img_2) > 0.99);
+}
+
+/// Root-mean-squared similarity of two RGB images, computed in parallel
+///
+/// This gives the same score as `image_compare`'s `RootMeanSquared` algorithm:
+/// `1 - sqrt(mean((diff / 255)^2))` for each channel, keeping the worst
+/// channel. Instead of building a per-pixel similarity image, squared
+/// differences are summed directly over `par_chunks` of the raw buffers.
+/// Two empty images have no pixel to differ, and score 1.0.
+pub fn rgb_similarity_parallel(img_1: &RgbImage, img_2: &RgbImage) -> f64 {
+    assert_eq!(
+        img_1.dimensions(),
+        img_2.dimensions(),
+        "Images must have the same dimensions"
+    );
+    if img_1.as_raw().is_empty() {
+        // The mean below would be 0 / 0
+        return 1.0;
+    }
+
+    // A multiple of 3 so every chunk starts on a red byte
+    const CHUNK_SIZE: usize = 3 * 4096;
+
+    let sums = img_1
+        .as_raw()
+        .par_chunks(CHUNK_SIZE)
+        .zip(img_2.as_raw().par_chunks(CHUNK_SIZE))
+        .map(|(chunk_1, chunk_2)| {
+            let mut sums = [0u64; 3];
+            for (pixel_1, pixel_2) in chunk_1.chunks_exact(3).zip(chunk_2.chunks_exact(3)) {
+                for channel in 0..3 {
+                    let diff = pixel_1[channel].abs_diff(pixel_2[channel]) as u64;
+                    sums[channel] += diff * diff;
+                }
+            }
+            sums
+        })
+        .reduce(|| [0u64; 3], |a, b| [a[0] + b[0], a[1] + b[1], a[2] + b[2]]);
+
+    let pixels = (img_1.width() as u64 * img_1.height() as u64) as f64;
+    sums.iter()
+        .map(|&sum| 1.0 - (sum as f64 / (255.0 * 255.0) / pixels).sqrt())
+        .fold(1.0, f64::min)
 }
 
 pub fn assert_eq_gray_img(img_1: &GrayImage, img_2: &GrayImage) {
     let result = image_compare::gray_similarity_structure(
         &image_compare::Algorithm::RootMeanSquared,
-        &img_1,
-        &img_2,
+        img_1,
+        img_2,
     )
     .unwrap();
     assert!(result.score > 0.99);
 }
+
+/// Per-pixel structural similarity (SSIM) of two grayscale images, as an image
+///
+/// Each pixel holds the SSIM of the `window x window` neighborhood centered on
+/// it (cut at the borders), from 0 (black, unrelated or anti-correlated) to
+/// 255 (white, identical). Unlike a single score, the map shows where a
+/// filter regression happens. Window statistics come from summed-area tables,
+/// so the cost doesn't depend on the window size.
+pub fn ssim_map(a: &GrayImage, b: &GrayImage, window: u32) -> GrayImage {
+    assert_eq!(
+        a.dimensions(),
+        b.dimensions(),
+        "Images must have the same dimensions"
+    );
+    assert!(window > 0, "The window must be at least 1 pixel wide");
+
+    // Stabilizing constants from the original SSIM paper, for 8-bit values
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let (width, height) = a.dimensions();
+    let a_raw = a.as_raw();
+    let b_raw = b.as_raw();
+
+    let sum_a = SummedArea::new(width, height, |i| a_raw[i] as u64);
+    let sum_b = SummedArea::new(width, height, |i| b_raw[i] as u64);
+    let sum_aa = SummedArea::new(width, height, |i| a_raw[i] as u64 * a_raw[i] as u64);
+    let sum_bb = SummedArea::new(width, height, |i| b_raw[i] as u64 * b_raw[i] as u64);
+    let sum_ab = SummedArea::new(width, height, |i| a_raw[i] as u64 * b_raw[i] as u64);
+
+    let before = (window - 1) / 2;
+    let after = window / 2;
+
+    GrayImage::from_fn(width, height, |x, y| {
+        let x0 = x.saturating_sub(before);
+        let y0 = y.saturating_sub(before);
+        let x1 = (x + after + 1).min(width);
+        let y1 = (y + after + 1).min(height);
+        let n = ((x1 - x0) * (y1 - y0)) as f64;
+
+        let mean_a = sum_a.sum(x0, y0, x1, y1) as f64 / n;
+        let mean_b = sum_b.sum(x0, y0, x1, y1) as f64 / n;
+        let var_a = sum_aa.sum(x0, y0, x1, y1) as f64 / n - mean_a * mean_a;
+        let var_b = sum_bb.sum(x0, y0, x1, y1) as f64 / n - mean_b * mean_b;
+        let covariance = sum_ab.sum(x0, y0, x1, y1) as f64 / n - mean_a * mean_b;
+
+        let ssim = ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+            / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2));
+
+        image::Luma([(ssim.clamp(0.0, 1.0) * 255.0).round() as u8])
+    })
+}
+
+/// Summed-area table: `sum(x0, y0, x1, y1)` of any rectangle in 4 lookups
+struct SummedArea {
+    /// `(width + 1) * (height + 1)` prefix sums, with a leading row and column of zeros
+    sums: Vec<u64>,
+    stride: usize,
+}
+
+impl SummedArea {
+    /// Build the table of `value(i)` for every pixel index `i` in row-major order
+    fn new(width: u32, height: u32, value: impl Fn(usize) -> u64) -> Self {
+        let (width, height) = (width as usize, height as usize);
+        let stride = width + 1;
+        let mut sums = vec![0u64; stride * (height + 1)];
+
+        for y in 0..height {
+            let mut row_sum = 0;
+            for x in 0..width {
+                row_sum += value(y * width + x);
+                sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row_sum;
+            }
+        }
+
+        SummedArea { sums, stride }
+    }
+
+    /// Sum over `x0..x1` and `y0..y1`
+    fn sum(&self, x0: u32, y0: u32, x1: u32, y1: u32) -> u64 {
+        let at = |x: u32, y: u32| self.sums[y as usize * self.stride + x as usize];
+        at(x1, y1) + at(x0, y0) - at(x0, y1) - at(x1, y0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgb_similarity_parallel_matches_serial() {
+        let img_1: RgbImage =
+            ImageBuffer::from_fn(97, 61, |x, y| Rgb([x as u8, y as u8, (x * y) as u8]));
+        let img_2: RgbImage = ImageBuffer::from_fn(97, 61, |x, y| {
+            Rgb([x as u8 / 2, (y as u8).wrapping_add(20), (x * y) as u8])
+        });
+
+        for (a, b) in [(&img_1, &img_1), (&img_1, &img_2)] {
+            let serial = image_compare::rgb_similarity_structure(
+                &image_compare::Algorithm::RootMeanSquared,
+                a,
+                b,
+            )
+            .unwrap()
+            .score;
+            let parallel = rgb_similarity_parallel(a, b);
+
+            assert!(
+                (serial - parallel).abs() < 1e-6,
+                "Serial {serial} vs parallel {parallel}"
+            );
+        }
+        assert_eq!(rgb_similarity_parallel(&img_1, &img_1), 1.0);
+    }
+
+    #[test]
+    fn test_rgb_similarity_parallel_empty_images() {
+        for (width, height) in [(0, 0), (0, 5), (5, 0)] {
+            let empty = RgbImage::new(width, height);
+
+            assert_eq!(rgb_similarity_parallel(&empty, &empty), 1.0);
+            assert_eq_img_parallel(&empty, &empty);
+        }
+    }
+
+    /// Every channel off by 12: a similarity of 1 - 12/255, about 0.953
+    fn slightly_different_pair() -> (RgbImage, RgbImage) {
+        let img_1: RgbImage =
+            ImageBuffer::from_fn(8, 8, |x, y| Rgb([x as u8 * 20, y as u8 * 20, 100]));
+        let img_2: RgbImage = ImageBuffer::from_fn(8, 8, |x, y| {
+            Rgb([x as u8 * 20 + 12, y as u8 * 20 + 12, 112])
+        });
+        (img_1, img_2)
+    }
+
+    #[test]
+    fn test_similarity_threshold_passes_lower_bar() {
+        let (img_1, img_2) = slightly_different_pair();
+
+        assert_similarity_at_least(&img_1, &img_2, 0.9);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not above 0.99")]
+    fn test_similarity_threshold_fails_higher_bar() {
+        let (img_1, img_2) = slightly_different_pair();
+
+        assert_similarity_at_least(&img_1, &img_2, 0.99);
+    }
+
+    #[test]
+    fn test_build_rgb() {
+        let img = build_rgb(2, 2, vec![0u8; 12]).unwrap();
+        assert_eq!(img.dimensions(), (2, 2));
+
+        let gray: GrayImage = build_image(3, 2, vec![7u8; 6]).unwrap();
+        assert_eq!(gray.get_pixel(2, 1)[0], 7);
+    }
+
+    #[test]
+    fn test_build_rgb_wrong_length() {
+        for actual in [11, 13] {
+            assert_eq!(
+                build_rgb(2, 2, vec![0u8; actual]),
+                Err(FilterError::BufferSizeMismatch {
+                    width: 2,
+                    height: 2,
+                    expected: 12,
+                    actual,
+                })
+            );
+        }
+
+        let gray: Result<GrayImage, _> = build_image(3, 2, vec![0u8; 5]);
+        assert!(gray.is_err());
+    }
+
+    #[test]
+    fn test_ssim_map_identical_images_are_bright() {
+        let img = GrayImage::from_fn(40, 30, |x, y| image::Luma([((x * 7 + y * 13) % 256) as u8]));
+
+        for window in [1, 7, 8, 100] {
+            let map = ssim_map(&img, &img, window);
+
+            assert_eq!(map.dimensions(), (40, 30));
+            assert!(map.pixels().all(|pixel| pixel[0] == 255), "Window {window}");
+        }
+    }
+
+    #[test]
+    fn test_ssim_map_locates_differences() {
+        let img = GrayImage::from_fn(40, 30, |x, y| image::Luma([((x * 7 + y * 13) % 256) as u8]));
+        // Invert a patch in the bottom right corner
+        let mut modified = img.clone();
+        for y in 20..30 {
+            for x in 30..40 {
+                modified.get_pixel_mut(x, y)[0] = 255 - img.get_pixel(x, y)[0];
+            }
+        }
+
+        let map = ssim_map(&img, &modified, 7);
+
+        assert_eq!(map.get_pixel(5, 5)[0], 255);
+        assert!(map.get_pixel(35, 25)[0] < 64);
+    }
+
+    #[test]
+    fn test_fixture_images_corners() {
+        let gradient = gradient_image(256, 100);
+        assert_eq!(gradient.dimensions(), (256, 100));
+        assert_eq!(gradient.get_pixel(0, 0), &Rgb([0, 0, 255]));
+        assert_eq!(gradient.get_pixel(255, 0), &Rgb([255, 0, 0]));
+        assert_eq!(gradient.get_pixel(0, 99), &Rgb([0, 253, 255]));
+        assert_eq!(gradient.get_pixel(255, 99), &Rgb([255, 253, 0]));
+        assert_eq!(gradient_image(256, 100), gradient);
+
+        let (a, b) = (Rgb([10, 20, 30]), Rgb([200, 210, 220]));
+        let checkerboard = checkerboard_image(5, 4, a, b);
+        assert_eq!(checkerboard.dimensions(), (5, 4));
+        assert_eq!(checkerboard.get_pixel(0, 0), &a);
+        assert_eq!(checkerboard.get_pixel(4, 0), &a);
+        assert_eq!(checkerboard.get_pixel(0, 3), &b);
+        assert_eq!(checkerboard.get_pixel(4, 3), &b);
+        assert_eq!(checkerboard.get_pixel(1, 0), &b);
+    }
+}
@@ -1,10 +1,18 @@
 #![feature(portable_simd)]
 
+pub mod batch;
 pub mod bfs;
 pub mod blob_corruption_checker;
+pub mod blur;
+pub mod color_filters;
 pub mod dna_matcher;
+pub mod fasta;
 pub mod helpers;
 pub mod lut_filters;
 pub mod lut_grayscale;
+pub mod nucleotide;
+pub mod pipeline;
 pub mod simd_brightness;
 pub mod simd_filters;
+pub mod simd_grayscale;
+pub mod transform;
@@ -15,7 +15,13 @@
 /// - Understand space-time tradeoffs
 /// - See dramatic speedups (especially for gamma: 50-100x!)
 /// - Learn when LUTs are appropriate
+use std::simd::num::{SimdFloat, SimdUint};
+use std::simd::{Simd, StdFloat, f32x16, u16x16};
+
 use image::{ImageBuffer, Rgb, RgbImage};
+use rayon::prelude::*;
+
+use crate::helpers::{build_image, build_rgb};
 
 pub fn apply_brightness_contrast(img: &RgbImage, brightness: i16, contrast: f32) -> RgbImage {
     naive::apply_brightness_contrast(img, brightness, contrast)
@@ -35,6 +41,239 @@ pub fn apply_brightness_contrast_gamma(
     naive::apply_gamma(&temp_img, gamma)
 }
 
+/// Brightness/contrast then gamma, fused into a single 256-entry table
+///
+/// Each filter maps a channel value to another one independently of the rest
+/// of the pixel, so chaining them is just composing their tables. The
+/// result matches [`apply_brightness_contrast_gamma`] exactly.
+pub fn combined_lut(brightness: i16, contrast: f32, gamma: f32) -> [u8; 256] {
+    std::array::from_fn(|i| {
+        let value = ((i as f32 - 128.0) * (1.0 + contrast)) + 128.0 + brightness as f32;
+        let value = value.clamp(0.0, 255.0) as u8;
+        ((value as f32 / 255.0).powf(1.0 / gamma) * 255.0) as u8
+    })
+}
+
+/// Single pass over the image with a table built by [`combined_lut`]
+pub fn apply_combined_lut(img: &RgbImage, lut: &[u8; 256]) -> RgbImage {
+    let (width, height) = img.dimensions();
+    let output = img
+        .as_raw()
+        .iter()
+        .map(|&value| lut[value as usize])
+        .collect();
+
+    build_rgb(width, height, output).expect("output buffer is sized from the input image")
+}
+
+/// Same as [`apply_combined_lut`], with the output split across rayon tasks
+///
+/// Every byte is independent, so the output is cut into fixed-size blocks
+/// each filled from the matching block of the input.
+pub fn apply_combined_lut_parallel(img: &RgbImage, lut: &[u8; 256]) -> RgbImage {
+    const BLOCK_SIZE: usize = 64 * 1024;
+
+    let (width, height) = img.dimensions();
+    let input = img.as_raw();
+    let mut output = vec![0u8; input.len()];
+
+    output
+        .par_chunks_mut(BLOCK_SIZE)
+        .zip(input.par_chunks(BLOCK_SIZE))
+        .for_each(|(out_block, in_block)| {
+            for (out, &value) in out_block.iter_mut().zip(in_block) {
+                *out = lut[value as usize];
+            }
+        });
+
+    build_rgb(width, height, output).expect("output buffer is sized from the input image")
+}
+
+/// How [`apply_contrast_mode`] builds its contrast curve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContrastMode {
+    /// Scale values away from 128 by `1 + amount`, like [`apply_brightness_contrast`]
+    Linear,
+    /// S-curve `1 / (1 + e^(-amount * (x - 0.5)))` over `x` in 0..=1,
+    /// rescaled so 0 and 255 stay put. Compresses the extremes instead of
+    /// clipping them; `amount` is the steepness (around 5 to 10 is typical)
+    Sigmoid,
+    /// Stretch the histogram: the `amount` fraction (e.g. 0.01) of darkest
+    /// channel values maps to 0, and the brightest fraction to 255
+    Percentile,
+}
+
+/// Contrast adjustment through a 256-entry table, with a choice of curve
+pub fn apply_contrast_mode(img: &RgbImage, amount: f32, mode: ContrastMode) -> RgbImage {
+    let lut = contrast_lut(img, amount, mode);
+    apply_combined_lut(img, &lut)
+}
+
+/// The table used by [`apply_contrast_mode`]
+///
+/// Only [`ContrastMode::Percentile`] looks at the image, to build its histogram.
+pub fn contrast_lut(img: &RgbImage, amount: f32, mode: ContrastMode) -> [u8; 256] {
+    match mode {
+        ContrastMode::Linear => std::array::from_fn(|i| {
+            let value = (i as f32 - 128.0) * (1.0 + amount) + 128.0;
+            value.clamp(0.0, 255.0) as u8
+        }),
+        ContrastMode::Sigmoid => {
+            // A flat sigmoid is a straight line, and 0 / 0 once rescaled
+            if amount <= f32::EPSILON {
+                return std::array::from_fn(|i| i as u8);
+            }
+
+            let sigmoid = |x: f32| 1.0 / (1.0 + (-amount * (x - 0.5)).exp());
+            let (low, high) = (sigmoid(0.0), sigmoid(1.0));
+            std::array::from_fn(|i| {
+                let curved = (sigmoid(i as f32 / 255.0) - low) / (high - low);
+                (curved * 255.0).round().clamp(0.0, 255.0) as u8
+            })
+        }
+        ContrastMode::Percentile => {
+            let mut histogram = [0u64; 256];
+            for &value in img.as_raw() {
+                histogram[value as usize] += 1;
+            }
+
+            let total: u64 = histogram.iter().sum();
+            let clipped = (total as f64 * amount.clamp(0.0, 0.5) as f64) as u64;
+            let low = first_past(&histogram, clipped, 0..256);
+            let high = first_past(&histogram, clipped, (0..256).rev());
+
+            if high <= low {
+                return std::array::from_fn(|i| i as u8);
+            }
+            std::array::from_fn(|i| {
+                let stretched = (i as f32 - low as f32) / (high - low) as f32 * 255.0;
+                stretched.round().clamp(0.0, 255.0) as u8
+            })
+        }
+    }
+}
+
+/// The first value of `values` at which the histogram counts seen so far exceed `skipped`
+fn first_past(histogram: &[u64; 256], skipped: u64, values: impl Iterator<Item = usize>) -> usize {
+    let mut seen = 0;
+    for value in values {
+        seen += histogram[value];
+        if seen > skipped {
+            return value;
+        }
+    }
+    0
+}
+
+/// Gamma correction between configurable black and white points, through a 256-entry table
+///
+/// Video usually stores limited range levels (16 to 235) instead of the full
+/// 0 to 255. Inputs are normalized from `in_black..=in_white` (clamping values
+/// outside it), go through the same `x^(1/gamma)` curve as [`apply_gamma`],
+/// and are scaled back to `out_black..=out_white`.
+pub fn apply_gamma_range(
+    img: &RgbImage,
+    gamma: f32,
+    in_black: u8,
+    in_white: u8,
+    out_black: u8,
+    out_white: u8,
+) -> RgbImage {
+    let lut = gamma_range_lut(gamma, in_black, in_white, out_black, out_white);
+    apply_combined_lut(img, &lut)
+}
+
+/// The table used by [`apply_gamma_range`]
+pub fn gamma_range_lut(
+    gamma: f32,
+    in_black: u8,
+    in_white: u8,
+    out_black: u8,
+    out_white: u8,
+) -> [u8; 256] {
+    assert!(in_black < in_white, "in_black must be below in_white");
+
+    let in_range = (in_white - in_black) as f32;
+    let out_range = out_white as f32 - out_black as f32;
+    std::array::from_fn(|i| {
+        let x = ((i as f32 - in_black as f32) / in_range).clamp(0.0, 1.0);
+        let value = out_black as f32 + x.powf(1.0 / gamma) * out_range;
+        value.round().clamp(0.0, 255.0) as u8
+    })
+}
+
+/// Number of intervals of the sparse gamma LUT
+const GAMMA_LUT_STEPS: usize = 256;
+
+/// Gamma correction for 16-bit images, with an interpolated sparse LUT
+///
+/// A full LUT would need 65536 entries per gamma value, so only 257 samples of
+/// the curve are stored and values in between are linearly interpolated, 16
+/// channels at a time.
+///
+/// Sampling the curve at evenly spaced inputs works poorly: for gamma > 1 it
+/// is nearly vertical close to 0, and the first interval alone is off by more
+/// than 1000 (out of 65535) for gamma 2.2. Instead, samples are evenly spaced
+/// in `sqrt(x)`, which packs them densely near 0. Compared with `powf` on
+/// every value of the u16 range, the maximum error is about 3 for gamma 2.2,
+/// 2 for gamma 0.5 and 27 for a steep gamma of 3.0.
+pub fn apply_gamma_interp(
+    img: &ImageBuffer<Rgb<u16>, Vec<u16>>,
+    gamma: f32,
+) -> ImageBuffer<Rgb<u16>, Vec<u16>> {
+    const LANES: usize = 16;
+
+    // lut[k] = gamma curve at x = (k / STEPS)^2, scaled to the u16 range
+    let lut: Vec<f32> = (0..=GAMMA_LUT_STEPS)
+        .map(|k| {
+            let x = (k as f32 / GAMMA_LUT_STEPS as f32).powi(2);
+            x.powf(1.0 / gamma) * u16::MAX as f32
+        })
+        .collect();
+
+    let (width, height) = img.dimensions();
+    let input = img.as_raw();
+    let mut output = vec![0u16; input.len()];
+
+    let chunks = input.chunks_exact(LANES);
+    let remainder = chunks.remainder();
+
+    for (i, chunk) in chunks.enumerate() {
+        let values: f32x16 = u16x16::from_slice(chunk).cast();
+
+        // Position on the LUT: sqrt of the normalized value, in intervals
+        let position = (values * f32x16::splat(1.0 / u16::MAX as f32)).sqrt()
+            * f32x16::splat(GAMMA_LUT_STEPS as f32);
+        let index = position
+            .floor()
+            .simd_min(f32x16::splat((GAMMA_LUT_STEPS - 1) as f32));
+        let fraction = position - index;
+
+        let index: Simd<usize, LANES> = index.cast();
+        let low = f32x16::gather_or_default(&lut, index);
+        let high = f32x16::gather_or_default(&lut, index + Simd::splat(1));
+
+        let corrected = (low + fraction * (high - low))
+            .round()
+            .simd_clamp(f32x16::splat(0.0), f32x16::splat(u16::MAX as f32));
+        let corrected: u16x16 = corrected.cast();
+        corrected.copy_to_slice(&mut output[i * LANES..(i + 1) * LANES]);
+    }
+
+    // Handle remaining values
+    let tail_start = input.len() - remainder.len();
+    for (i, &value) in remainder.iter().enumerate() {
+        let position = (value as f32 / u16::MAX as f32).sqrt() * GAMMA_LUT_STEPS as f32;
+        let index = (position as usize).min(GAMMA_LUT_STEPS - 1);
+        let fraction = position - index as f32;
+
+        let corrected = lut[index] + fraction * (lut[index + 1] - lut[index]);
+        output[tail_start + i] = corrected.round().clamp(0.0, u16::MAX as f32) as u16;
+    }
+
+    build_image(width, height, output).expect("output buffer is sized from the input image")
+}
+
 mod naive {
     use super::*;
 
@@ -166,6 +405,118 @@ mod tests {
         assert_eq!(hash_image(&result), 9063327795097964491);
     }
 
+    #[test]
+    fn test_combined_lut_matches_separate_filters() {
+        let img = ImageBuffer::from_fn(64, 33, |x, y| {
+            Rgb([(x * 4) as u8, (y * 7) as u8, ((x * y) % 256) as u8])
+        });
+
+        for (brightness, contrast, gamma) in [(20, 0.5, 2.2), (-50, -0.3, 0.5), (0, 0.0, 1.0)] {
+            let lut = combined_lut(brightness, contrast, gamma);
+            let expected = apply_brightness_contrast_gamma(&img, brightness, contrast, gamma);
+
+            assert_eq!(apply_combined_lut(&img, &lut), expected);
+            assert_eq!(apply_combined_lut_parallel(&img, &lut), expected);
+        }
+    }
+
+    #[test]
+    fn test_combined_lut_parallel_large_image() {
+        // Several blocks, the last one partial
+        let img = ImageBuffer::from_fn(301, 200, |x, y| {
+            Rgb([(x % 256) as u8, (y % 256) as u8, ((x + y) % 256) as u8])
+        });
+        let lut = combined_lut(30, 0.3, 2.2);
+
+        assert_eq!(
+            apply_combined_lut_parallel(&img, &lut),
+            apply_combined_lut(&img, &lut)
+        );
+    }
+
+    #[test]
+    fn test_contrast_modes() {
+        // Channel values spread evenly over 50..=200
+        let img = ImageBuffer::from_fn(151, 1, |x, _| Rgb([50 + x as u8; 3]));
+
+        let linear = contrast_lut(&img, 0.5, ContrastMode::Linear);
+        assert_eq!((linear[128], linear[160]), (128, 176));
+
+        let sigmoid = contrast_lut(&img, 10.0, ContrastMode::Sigmoid);
+        assert_eq!(
+            (sigmoid[0], sigmoid[128], sigmoid[160], sigmoid[255]),
+            (0, 129, 200, 255)
+        );
+
+        let percentile = contrast_lut(&img, 0.0, ContrastMode::Percentile);
+        assert_eq!(
+            (percentile[50], percentile[128], percentile[200]),
+            (0, 133, 255)
+        );
+
+        let stretched = apply_contrast_mode(&img, 0.0, ContrastMode::Percentile);
+        assert_eq!(stretched.get_pixel(78, 0), &Rgb([133; 3]));
+        assert_eq!(
+            apply_contrast_mode(&img, 0.5, ContrastMode::Linear),
+            apply_brightness_contrast(&img, 0, 0.5)
+        );
+    }
+
+    #[test]
+    fn test_gamma_range_black_and_white_points() {
+        // Limited range in and out: the black and white points stay put, and
+        // values outside of the range are clamped to them
+        let limited = gamma_range_lut(2.2, 16, 235, 16, 235);
+        assert_eq!(
+            (limited[0], limited[16], limited[235], limited[255]),
+            (16, 16, 235, 235)
+        );
+        // Mid-gray of the range goes up with gamma 2.2, like in full range
+        assert!(limited[125] > 125);
+
+        // Gamma 1.0 only remaps the levels
+        let to_limited = gamma_range_lut(1.0, 0, 255, 16, 235);
+        assert_eq!((to_limited[0], to_limited[255]), (16, 235));
+        let to_full = gamma_range_lut(1.0, 16, 235, 0, 255);
+        assert_eq!((to_full[16], to_full[235]), (0, 255));
+        for value in 16..=235 {
+            assert_eq!(gamma_range_lut(1.0, 16, 235, 16, 235)[value], value as u8);
+        }
+
+        let img = ImageBuffer::from_fn(3, 1, |x, _| Rgb([[0, 16, 235][x as usize]; 3]));
+        let corrected = apply_gamma_range(&img, 2.2, 16, 235, 16, 235);
+        assert_eq!(corrected.as_raw(), &[16, 16, 16, 16, 16, 16, 235, 235, 235]);
+    }
+
+    #[test]
+    fn test_apply_gamma_interp_accuracy() {
+        // Every u16 value in every channel: 256 x 256 pixels of 3 channels
+        let values: Vec<u16> = (0..=u16::MAX)
+            .chain(0..=u16::MAX)
+            .chain(0..=u16::MAX)
+            .collect();
+        let img = ImageBuffer::<Rgb<u16>, Vec<u16>>::from_raw(256, 256, values).unwrap();
+
+        for (gamma, max_allowed) in [(2.2, 4.0), (1.8, 4.0), (0.5, 4.0), (1.0, 1.0)] {
+            let result = apply_gamma_interp(&img, gamma);
+
+            let max_error = img
+                .as_raw()
+                .iter()
+                .zip(result.as_raw())
+                .map(|(&input, &output)| {
+                    let exact = (input as f64 / 65535.0).powf(1.0 / gamma as f64) * 65535.0;
+                    (output as f64 - exact).abs()
+                })
+                .fold(0.0, f64::max);
+
+            assert!(
+                max_error <= max_allowed,
+                "Gamma {gamma}: max error {max_error} above {max_allowed}"
+            );
+        }
+    }
+
     #[test]
     fn test_gamma_extreme_values() {
         let img = create_test_image();
@@ -12,27 +12,69 @@
 /// ## The Solution: Lookup Tables
 /// Since RGB values are 0-255, we can pre-compute results and store them in arrays.
 /// This trades computation for memory access.
-use image::{GrayImage, ImageBuffer, Luma, RgbImage};
+use image::{GrayImage, ImageBuffer, Luma, Rgb, RgbImage, RgbaImage};
+use rayon::prelude::*;
+
+use crate::helpers::build_image;
 
 /// Pre-computed lookup tables for each RGB channel
-/// Memory: 768 bytes (3 * 256)
+/// Memory: 1536 bytes (3 * 256 * 2)
+///
+/// Entries are stored in 8.8 fixed point (the gray contribution times 256,
+/// rounded). Storing whole `u8` contributions would truncate each channel
+/// independently and lose up to 1 per channel: white would sum to
+/// 76 + 149 + 29 = 254 instead of 255. With 8 fractional bits the rounding
+/// error is at most 1/512 per channel, and only the final sum is truncated,
+/// like `rgb_to_gray_naive` does.
 pub struct GrayscaleLut {
-    red_lut: [u8; 256],
-    green_lut: [u8; 256],
-    blue_lut: [u8; 256],
+    red_lut: [u16; 256],
+    green_lut: [u16; 256],
+    blue_lut: [u16; 256],
+}
+
+/// Named luminance standards, each defining the R, G, B weights of the gray value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LuminanceStandard {
+    /// ITU-R BT.601 (SD video), the weights used by `rgb_to_gray_naive`
+    Bt601,
+    /// ITU-R BT.709 (HD video and sRGB)
+    Bt709,
+    /// ITU-R BT.2020 (UHD video)
+    Bt2020,
+    /// Plain average of the three channels
+    Average,
+}
+
+impl LuminanceStandard {
+    /// The (red, green, blue) weights, summing to 1
+    pub fn weights(self) -> (f32, f32, f32) {
+        match self {
+            LuminanceStandard::Bt601 => (0.299, 0.587, 0.114),
+            LuminanceStandard::Bt709 => (0.2126, 0.7152, 0.0722),
+            LuminanceStandard::Bt2020 => (0.2627, 0.678, 0.0593),
+            LuminanceStandard::Average => (1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0),
+        }
+    }
 }
 
 impl GrayscaleLut {
     /// Create a new lookup table with standard luminosity weights
     pub fn new() -> Self {
-        let mut red_lut = [0u8; 256];
-        let mut green_lut = [0u8; 256];
-        let mut blue_lut = [0u8; 256];
+        Self::for_standard(LuminanceStandard::Bt601)
+    }
+
+    /// Create a lookup table for the weights of a given luminance standard
+    pub fn for_standard(standard: LuminanceStandard) -> Self {
+        let (red_weight, green_weight, blue_weight) = standard.weights();
+
+        let mut red_lut = [0u16; 256];
+        let mut green_lut = [0u16; 256];
+        let mut blue_lut = [0u16; 256];
 
         for i in 0..256 {
-            red_lut[i] = (i as f32 * 0.299) as u8;
-            green_lut[i] = (i as f32 * 0.587) as u8;
-            blue_lut[i] = (i as f32 * 0.114) as u8;
+            red_lut[i] = (i as f32 * red_weight * 256.0).round() as u16;
+            green_lut[i] = (i as f32 * green_weight * 256.0).round() as u16;
+            blue_lut[i] = (i as f32 * blue_weight * 256.0).round() as u16;
         }
 
         Self {
@@ -41,6 +83,43 @@ impl GrayscaleLut {
             blue_lut,
         }
     }
+
+    /// Create a lookup table from fixed-point weights, without any float math
+    ///
+    /// Each weight is `weight / 2^shift`: the contribution of a channel is
+    /// `(channel * weight) >> shift`, kept here with 8 extra fractional bits
+    /// like the other tables. The BT.601 weights with `shift = 8` are
+    /// 77 / 150 / 29, which sum to exactly 256 so white stays 255. With
+    /// `shift = 16` they are 19595 / 38470 / 7471.
+    ///
+    /// The weights must sum to at most `2^shift`, and `shift` be at most 16.
+    pub fn from_fixed(red_weight: u16, green_weight: u16, blue_weight: u16, shift: u8) -> Self {
+        assert!(shift <= 16, "shift must be at most 16");
+        assert!(
+            red_weight as u32 + green_weight as u32 + blue_weight as u32 <= 1 << shift,
+            "fixed-point weights must sum to at most 1"
+        );
+
+        // Rounded to the nearest 1/256, like the float tables
+        let half = (1u32 << shift) >> 1;
+        let table = |weight: u16| -> [u16; 256] {
+            std::array::from_fn(|i| ((((i as u32 * weight as u32) << 8) + half) >> shift) as u16)
+        };
+
+        Self {
+            red_lut: table(red_weight),
+            green_lut: table(green_weight),
+            blue_lut: table(blue_weight),
+        }
+    }
+
+    /// Gray value of one pixel: 3 lookups, then drop the fractional bits of the sum
+    fn gray(&self, r: u8, g: u8, b: u8) -> u8 {
+        let sum = self.red_lut[r as usize] as u32
+            + self.green_lut[g as usize] as u32
+            + self.blue_lut[b as usize] as u32;
+        (sum >> 8).min(255) as u8
+    }
 }
 
 impl Default for GrayscaleLut {
@@ -57,30 +136,57 @@ impl Default for GrayscaleLut {
 /// - Single array lookup per pixel (no additions!)
 /// - May not fit in CPU cache
 pub struct GrayscaleLutBig {
-    lut: Box<[[[u8; 256]; 256]; 256]>,
+    /// Flat `256 * 256 * 256` table, indexed by `r << 16 | g << 8 | b`
+    lut: Box<[u8]>,
 }
 
 impl GrayscaleLutBig {
+    /// Create a table with the BT.601 weights, like [`rgb_to_gray_naive`]
     pub fn new() -> Self {
-        // Allocate directly on heap to avoid stack overflow
-        let mut lut = vec![0u8; 256 * 256 * 256].into_boxed_slice();
-
-        for r in 0..256 {
-            for g in 0..256 {
-                for b in 0..256 {
-                    let gray = (r as f32 * 0.299 + g as f32 * 0.587 + b as f32 * 0.114) as u8;
-                    let idx = r * 256 * 256 + g * 256 + b;
-                    lut[idx] = gray;
-                }
-            }
-        }
+        Self::with_weights(0.299, 0.587, 0.114)
+    }
 
-        // Convert Box<[u8]> to the proper array type
-        let lut_ptr = Box::into_raw(lut) as *mut [[[u8; 256]; 256]; 256];
-        let lut = unsafe { Box::from_raw(lut_ptr) };
+    /// Create a table for arbitrary (red, green, blue) weights, e.g. from
+    /// [`LuminanceStandard::weights`]
+    pub fn with_weights(red_weight: f32, green_weight: f32, blue_weight: f32) -> Self {
+        // Built directly on the heap: a 16 MB array would overflow the stack
+        let lut = (0..256 * 256 * 256)
+            .map(|index: usize| {
+                let (r, g, b) = (index >> 16, (index >> 8) & 0xFF, index & 0xFF);
+                (r as f32 * red_weight + g as f32 * green_weight + b as f32 * blue_weight) as u8
+            })
+            .collect();
 
         Self { lut }
     }
+
+    /// Look up the gray value of an RGB triple
+    #[inline]
+    pub fn get(&self, r: u8, g: u8, b: u8) -> u8 {
+        self.lut[Self::index(r, g, b)]
+    }
+
+    #[inline]
+    fn index(r: u8, g: u8, b: u8) -> usize {
+        (r as usize) << 16 | (g as usize) << 8 | b as usize
+    }
+
+    /// Hint the CPU to start loading the entry of an RGB triple into cache
+    ///
+    /// A no-op on targets other than x86_64.
+    #[inline]
+    fn prefetch(&self, r: u8, g: u8, b: u8) {
+        #[cfg(target_arch = "x86_64")]
+        {
+            use std::arch::x86_64::{_MM_HINT_T0, _mm_prefetch};
+
+            let entry: *const u8 = &self.lut[Self::index(r, g, b)];
+            // SAFETY: prefetching never faults, and SSE is always available on x86_64
+            unsafe { _mm_prefetch::<_MM_HINT_T0>(entry.cast()) };
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        let _ = (r, g, b);
+    }
 }
 
 impl Default for GrayscaleLutBig {
@@ -118,20 +224,14 @@ pub fn rgb_to_gray_naive(img: &RgbImage) -> GrayImage {
 /// This should be MUCH faster than the naive version because:
 /// 1. No floating-point operations per pixel
 /// 2. Only 3 array lookups + 2 integer additions
-/// 3. Better CPU cache locality (768 bytes fits in L1 cache)
+/// 3. Better CPU cache locality (1536 bytes fits in L1 cache)
 pub fn rgb_to_gray_small_lut(img: &RgbImage, lut: &GrayscaleLut) -> GrayImage {
     let (width, height) = img.dimensions();
     let mut gray_img = ImageBuffer::new(width, height);
 
     for (x, y, pixel) in img.enumerate_pixels() {
-        let r = pixel[0] as usize;
-        let g = pixel[1] as usize;
-        let b = pixel[2] as usize;
-
         // Use lookup tables instead of computing
-        let gray_value = lut.red_lut[r]
-            .saturating_add(lut.green_lut[g])
-            .saturating_add(lut.blue_lut[b]);
+        let gray_value = lut.gray(pixel[0], pixel[1], pixel[2]);
 
         gray_img.put_pixel(x, y, Luma([gray_value]));
     }
@@ -150,12 +250,8 @@ pub fn rgb_to_gray_big_lut(img: &RgbImage, lut: &GrayscaleLutBig) -> GrayImage {
     let mut gray_img = ImageBuffer::new(width, height);
 
     for (x, y, pixel) in img.enumerate_pixels() {
-        let r = pixel[0] as usize;
-        let g = pixel[1] as usize;
-        let b = pixel[2] as usize;
-
         // Single lookup - all values pre-computed!
-        let gray_value = lut.lut[r][g][b];
+        let gray_value = lut.get(pixel[0], pixel[1], pixel[2]);
 
         gray_img.put_pixel(x, y, Luma([gray_value]));
     }
@@ -163,6 +259,152 @@ pub fn rgb_to_gray_big_lut(img: &RgbImage, lut: &GrayscaleLutBig) -> GrayImage {
     gray_img
 }
 
+/// Split an RGB image into its red, green and blue planes
+pub fn split_channels(img: &RgbImage) -> [GrayImage; 3] {
+    let (width, height) = img.dimensions();
+    let mut planes = [(); 3].map(|_| Vec::with_capacity(width as usize * height as usize));
+
+    for &[r, g, b] in img.as_raw().as_chunks::<3>().0 {
+        planes[0].push(r);
+        planes[1].push(g);
+        planes[2].push(b);
+    }
+
+    planes.map(|plane| {
+        build_image(width, height, plane).expect("output buffer is sized from the input image")
+    })
+}
+
+/// Small LUT conversion that can also output the source channels as planes
+///
+/// Some consumers (e.g. GPU uploads) want planar data next to the gray
+/// image. With `with_planes`, the red, green and blue planes are filled in the
+/// same pass over the pixels as the gray value, instead of reading the whole
+/// image a second time with [`split_channels`].
+pub fn rgb_to_gray_planar(
+    img: &RgbImage,
+    lut: &GrayscaleLut,
+    with_planes: bool,
+) -> (GrayImage, Option<[GrayImage; 3]>) {
+    let (width, height) = img.dimensions();
+    let (pixels, _) = img.as_raw().as_chunks::<3>();
+
+    let mut gray = Vec::with_capacity(pixels.len());
+
+    let planes = if with_planes {
+        let mut planes = [(); 3].map(|_| Vec::with_capacity(pixels.len()));
+        for &[r, g, b] in pixels {
+            gray.push(lut.gray(r, g, b));
+            planes[0].push(r);
+            planes[1].push(g);
+            planes[2].push(b);
+        }
+        Some(planes.map(|plane| {
+            build_image(width, height, plane).expect("output buffer is sized from the input image")
+        }))
+    } else {
+        gray.extend(pixels.iter().map(|&[r, g, b]| lut.gray(r, g, b)));
+        None
+    };
+
+    let gray =
+        build_image(width, height, gray).expect("output buffer is sized from the input image");
+    (gray, planes)
+}
+
+/// Grayscale conversion that keeps the image compositable
+///
+/// R, G and B are all set to the BT.601 luminance of the pixel, from a
+/// [`GrayscaleLut`], and alpha is copied through unchanged.
+pub fn rgba_to_gray_rgba(img: &RgbaImage) -> RgbaImage {
+    let lut = GrayscaleLut::new();
+    let (width, height) = img.dimensions();
+
+    let pixels = img
+        .as_raw()
+        .as_chunks::<4>()
+        .0
+        .iter()
+        .flat_map(|&[r, g, b, a]| {
+            let gray = lut.gray(r, g, b);
+            [gray, gray, gray, a]
+        })
+        .collect();
+
+    build_image(width, height, pixels).expect("output buffer is sized from the input image")
+}
+
+/// Grayscale conversion of a float (HDR) image, with the BT.601 weights
+///
+/// A LUT needs a small set of possible inputs, which floats don't have, so the
+/// weighted sum is computed for every pixel. Nothing is clamped or rounded:
+/// values above 1.0 (highlights brighter than the display white) and below
+/// 0.0 keep their full range.
+pub fn rgb_f32_to_gray(img: &ImageBuffer<Rgb<f32>, Vec<f32>>) -> ImageBuffer<Luma<f32>, Vec<f32>> {
+    let (red_weight, green_weight, blue_weight) = LuminanceStandard::Bt601.weights();
+    let (width, height) = img.dimensions();
+
+    let gray = img
+        .as_raw()
+        .as_chunks::<3>()
+        .0
+        .iter()
+        .map(|&[r, g, b]| r * red_weight + g * green_weight + b * blue_weight)
+        .collect();
+
+    build_image(width, height, gray).expect("output buffer is sized from the input image")
+}
+
+/// Method-style grayscale conversions, e.g. `img.to_grayscale_lut(&lut)`
+pub trait GrayscaleExt {
+    /// See [`rgb_to_gray_small_lut`]
+    fn to_grayscale_lut(&self, lut: &GrayscaleLut) -> GrayImage;
+    /// See [`rgb_to_gray_naive`]
+    fn to_grayscale_naive(&self) -> GrayImage;
+}
+
+impl GrayscaleExt for RgbImage {
+    fn to_grayscale_lut(&self, lut: &GrayscaleLut) -> GrayImage {
+        rgb_to_gray_small_lut(self, lut)
+    }
+
+    fn to_grayscale_naive(&self) -> GrayImage {
+        rgb_to_gray_naive(self)
+    }
+}
+
+/// Big lookup table, rows processed in parallel with software prefetching
+///
+/// Neighboring pixels rarely hit neighboring LUT entries, so most lookups are
+/// cache misses. While the current pixel is looked up, the entry of the next
+/// pixel is prefetched so its cache miss overlaps with the current work.
+pub fn rgb_to_gray_big_lut_parallel(img: &RgbImage, lut: &GrayscaleLutBig) -> GrayImage {
+    let (width, height) = img.dimensions();
+    let row_len = width as usize;
+
+    let mut output = vec![0u8; row_len * height as usize];
+    if row_len == 0 {
+        return build_image(width, height, output)
+            .expect("output buffer is sized from the input image");
+    }
+
+    output
+        .par_chunks_mut(row_len)
+        .zip(img.as_raw().par_chunks(row_len * 3))
+        .for_each(|(gray_row, rgb_row)| {
+            let (pixels, _) = rgb_row.as_chunks::<3>();
+
+            for (i, (gray, &[r, g, b])) in gray_row.iter_mut().zip(pixels).enumerate() {
+                if let Some(&[next_r, next_g, next_b]) = pixels.get(i + 1) {
+                    lut.prefetch(next_r, next_g, next_b);
+                }
+                *gray = lut.get(r, g, b);
+            }
+        });
+
+    build_image(width, height, output).expect("output buffer is sized from the input image")
+}
+
 #[cfg(test)]
 mod tests {
     use crate::helpers::assert_eq_gray_img;
@@ -216,6 +458,126 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_small_lut_white_is_255() {
+        let white = ImageBuffer::from_pixel(1, 1, Rgb([255u8, 255, 255]));
+
+        for standard in [
+            LuminanceStandard::Bt601,
+            LuminanceStandard::Bt709,
+            LuminanceStandard::Bt2020,
+            LuminanceStandard::Average,
+        ] {
+            let lut = GrayscaleLut::for_standard(standard);
+            let gray = rgb_to_gray_small_lut(&white, &lut);
+
+            assert_eq!(gray.get_pixel(0, 0)[0], 255, "{standard:?}");
+        }
+        assert_eq!(rgb_to_gray_naive(&white).get_pixel(0, 0)[0], 255);
+    }
+
+    #[test]
+    fn test_fixed_point_lut_matches_float_lut() {
+        let float_lut = GrayscaleLut::new();
+
+        for fixed_lut in [
+            GrayscaleLut::from_fixed(77, 150, 29, 8),
+            GrayscaleLut::from_fixed(19595, 38470, 7471, 16),
+        ] {
+            for r in 0..=255 {
+                for g in 0..=255 {
+                    for b in 0..=255 {
+                        let fixed = fixed_lut.gray(r, g, b);
+                        let float = float_lut.gray(r, g, b);
+                        assert!(
+                            fixed.abs_diff(float) <= 1,
+                            "({r}, {g}, {b}): fixed {fixed} vs float {float}"
+                        );
+                    }
+                }
+            }
+            assert_eq!(fixed_lut.gray(255, 255, 255), 255);
+        }
+    }
+
+    #[test]
+    fn test_grayscale_ext() {
+        let img = ImageBuffer::from_fn(5, 3, |x, y| Rgb([(x * 50) as u8, (y * 90) as u8, 200]));
+        let lut = GrayscaleLut::new();
+
+        assert_eq!(
+            img.to_grayscale_lut(&lut),
+            rgb_to_gray_small_lut(&img, &lut)
+        );
+        assert_eq!(img.to_grayscale_naive(), rgb_to_gray_naive(&img));
+    }
+
+    #[test]
+    fn test_luminance_standards_green() {
+        let green = ImageBuffer::from_pixel(1, 1, Rgb([0u8, 255, 0]));
+        let gray_of_green = |standard| {
+            let lut = GrayscaleLut::for_standard(standard);
+            rgb_to_gray_small_lut(&green, &lut).get_pixel(0, 0)[0]
+        };
+
+        assert_eq!(gray_of_green(LuminanceStandard::Bt601), 149);
+        assert_eq!(gray_of_green(LuminanceStandard::Bt709), 182);
+        assert_eq!(gray_of_green(LuminanceStandard::Average), 85);
+    }
+
+    #[test]
+    fn test_big_lut_get_corners() {
+        let lut = GrayscaleLutBig::new();
+
+        assert_eq!(lut.get(0, 0, 0), 0);
+        assert_eq!(lut.get(255, 255, 255), 255);
+        assert_eq!(lut.get(255, 0, 0), 76);
+        assert_eq!(lut.get(0, 0, 255), 29);
+    }
+
+    #[test]
+    fn test_rgb_to_gray_big_lut_parallel_matches_serial() {
+        let img = ImageBuffer::from_fn(37, 23, |x, y| {
+            Rgb([(x * 7) as u8, (y * 11) as u8, ((x * y) % 256) as u8])
+        });
+        let lut = GrayscaleLutBig::new();
+
+        assert_eq!(
+            rgb_to_gray_big_lut_parallel(&img, &lut),
+            rgb_to_gray_big_lut(&img, &lut)
+        );
+    }
+
+    #[test]
+    fn test_big_lut_matches_previous_table() {
+        let lut = GrayscaleLutBig::new();
+
+        assert_eq!(lut.lut.len(), 256 * 256 * 256);
+        assert_eq!(lut.get(128, 128, 128), 128);
+        assert_eq!(lut.get(0, 255, 0), 149);
+        assert_eq!(lut.get(10, 20, 30), 18);
+
+        // Checksum of the table built by the former `[[[u8; 256]; 256]; 256]` implementation
+        assert_eq!(crc32fast::hash(&lut.lut), 3738667546);
+    }
+
+    #[test]
+    fn test_big_lut_with_weights_bt709() {
+        let (r, g, b) = LuminanceStandard::Bt709.weights();
+        let big_lut = GrayscaleLutBig::with_weights(r, g, b);
+        let small_lut = GrayscaleLut::for_standard(LuminanceStandard::Bt709);
+
+        let white = ImageBuffer::from_pixel(1, 1, Rgb([255u8, 255, 255]));
+        let green = ImageBuffer::from_pixel(1, 1, Rgb([0u8, 255, 0]));
+        for img in [white, green] {
+            assert_eq!(
+                rgb_to_gray_big_lut(&img, &big_lut),
+                rgb_to_gray_small_lut(&img, &small_lut)
+            );
+        }
+        assert_eq!(big_lut.get(0, 255, 0), 182);
+    }
+
     #[test]
     fn test_rgb_to_gray_big_lut() {
         test_impl(|img| {
@@ -223,4 +585,68 @@ mod tests {
             rgb_to_gray_big_lut(img, &lut)
         });
     }
+
+    #[test]
+    fn test_rgb_to_gray_planar() {
+        let img = ImageBuffer::from_fn(7, 5, |x, y| {
+            Rgb([(x * 36) as u8, (y * 60) as u8, (x * y * 7) as u8])
+        });
+        let lut = GrayscaleLut::new();
+
+        let (gray, planes) = rgb_to_gray_planar(&img, &lut, true);
+        assert_eq!(gray, rgb_to_gray_small_lut(&img, &lut));
+        assert_eq!(planes, Some(split_channels(&img)));
+
+        let [red, green, blue] = split_channels(&img);
+        assert_eq!(red.get_pixel(3, 2)[0], 108);
+        assert_eq!(green.get_pixel(3, 2)[0], 120);
+        assert_eq!(blue.get_pixel(3, 2)[0], 42);
+
+        let (gray_only, planes) = rgb_to_gray_planar(&img, &lut, false);
+        assert_eq!(gray_only, gray);
+        assert!(planes.is_none());
+    }
+
+    #[test]
+    fn test_rgb_f32_to_gray_keeps_hdr_range() {
+        let img = ImageBuffer::from_fn(3, 1, |x, _| match x {
+            0 => Rgb([1.0f32, 1.0, 1.0]),
+            1 => Rgb([4.0, 2.0, 10.0]),
+            _ => Rgb([0.25, -0.5, 0.0]),
+        });
+        let gray = rgb_f32_to_gray(&img);
+
+        let expected = [
+            1.0,
+            4.0 * 0.299 + 2.0 * 0.587 + 10.0 * 0.114,
+            0.25 * 0.299 - 0.5 * 0.587,
+        ];
+        assert_eq!(gray.dimensions(), (3, 1));
+        for (x, expected) in expected.into_iter().enumerate() {
+            let value = gray.get_pixel(x as u32, 0)[0];
+            assert!(
+                (value - expected).abs() < 1e-5,
+                "Pixel {x}: {value} vs {expected}"
+            );
+        }
+        // Well above the display white
+        assert!(gray.get_pixel(1, 0)[0] > 3.5);
+    }
+
+    #[test]
+    fn test_rgba_to_gray_rgba_preserves_alpha() {
+        let img = RgbaImage::from_fn(16, 4, |x, y| {
+            image::Rgba([(x * 16) as u8, (y * 60) as u8, 200, (x * 16 + y) as u8])
+        });
+        let lut = GrayscaleLut::new();
+
+        let gray = rgba_to_gray_rgba(&img);
+
+        assert_eq!(gray.dimensions(), img.dimensions());
+        for (original, converted) in img.pixels().zip(gray.pixels()) {
+            let [r, g, b, a] = original.0;
+            let luminance = lut.gray(r, g, b);
+            assert_eq!(converted.0, [luminance, luminance, luminance, a]);
+        }
+    }
 }
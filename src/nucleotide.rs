@@ -0,0 +1,556 @@
+/// Compact nucleotide encodings
+///
+/// A, C, G and T only need 2 bits each, so a sequence can be stored 4 bases
+/// per byte: a quarter of the memory of the ASCII representation, and a
+/// quarter of the bytes to compare.
+use std::fmt;
+use std::simd::cmp::SimdPartialEq;
+use std::simd::num::SimdUint;
+use std::simd::{Select, Simd};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackError {
+    /// A byte other than A, C, G or T was found in the sequence
+    InvalidBase { position: usize, byte: u8 },
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackError::InvalidBase { position, byte } => {
+                write!(f, "invalid base {:?} at position {position}", *byte as char)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PackError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BaseError {
+    /// A byte other than A, C, G, T or N was found in the sequence
+    InvalidBase { position: usize, byte: u8 },
+}
+
+impl fmt::Display for BaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BaseError::InvalidBase { position, byte } => {
+                write!(f, "invalid base {:?} at position {position}", *byte as char)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BaseError {}
+
+const BASES: [u8; 4] = *b"ACGT";
+
+/// Complement of every byte, 0 for bytes that aren't a base
+const COMPLEMENT: [u8; 256] = {
+    let mut lut = [0u8; 256];
+    lut[b'A' as usize] = b'T';
+    lut[b'T' as usize] = b'A';
+    lut[b'C' as usize] = b'G';
+    lut[b'G' as usize] = b'C';
+    lut[b'N' as usize] = b'N';
+    lut
+};
+
+/// Pack a sequence of A/C/G/T into 2 bits per base
+///
+/// Bases are stored most-significant bits first: `ACGT` packs into the single
+/// byte `0b00_01_10_11`. A trailing partial byte is zero-padded, so the
+/// original length must be kept alongside to unpack.
+pub fn pack_2bit(sequence: &[u8]) -> Result<Vec<u8>, PackError> {
+    let mut packed = Vec::with_capacity(sequence.len().div_ceil(4));
+
+    for (chunk_index, chunk) in sequence.chunks(4).enumerate() {
+        let mut byte = 0u8;
+
+        for (i, &base) in chunk.iter().enumerate() {
+            let code = match base {
+                b'A' => 0,
+                b'C' => 1,
+                b'G' => 2,
+                b'T' => 3,
+                _ => {
+                    return Err(PackError::InvalidBase {
+                        position: chunk_index * 4 + i,
+                        byte: base,
+                    });
+                }
+            };
+            byte |= code << (6 - 2 * i);
+        }
+
+        packed.push(byte);
+    }
+
+    Ok(packed)
+}
+
+/// Unpack the first `len` bases of a sequence packed with [`pack_2bit`]
+pub fn unpack_2bit(packed: &[u8], len: usize) -> Vec<u8> {
+    assert!(
+        len <= packed.len() * 4,
+        "Cannot unpack {len} bases from {} bytes",
+        packed.len()
+    );
+
+    (0..len)
+        .map(|i| {
+            let code = (packed[i / 4] >> (6 - 2 * (i % 4))) & 0b11;
+            BASES[code as usize]
+        })
+        .collect()
+}
+
+/// Reverse complement of a whole sequence, as read from the opposite strand
+///
+/// A and T, C and G are swapped, and unknown bases (N) stay N.
+pub fn reverse_complement_sequence(sequence: &[u8]) -> Result<Vec<u8>, BaseError> {
+    sequence
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(position, &byte)| match byte {
+            b'A' => Ok(b'T'),
+            b'T' => Ok(b'A'),
+            b'C' => Ok(b'G'),
+            b'G' => Ok(b'C'),
+            b'N' => Ok(b'N'),
+            _ => Err(BaseError::InvalidBase { position, byte }),
+        })
+        .collect()
+}
+
+/// Same as [`reverse_complement_sequence`], 16 bases at a time
+///
+/// Chunks are taken from the end of the sequence, their lanes reversed, and
+/// every byte is complemented with a gather from a 256-entry lookup table.
+/// Invalid bytes map to 0 in the table, so a single comparison per chunk
+/// tells whether the whole chunk was valid.
+pub fn reverse_complement_simd(sequence: &[u8]) -> Result<Vec<u8>, BaseError> {
+    const LANES: usize = 16;
+
+    let mut output = vec![0u8; sequence.len()];
+
+    let chunks = sequence.rchunks_exact(LANES);
+    let remainder = chunks.remainder();
+
+    for (i, chunk) in chunks.enumerate() {
+        let reversed = Simd::<u8, LANES>::from_slice(chunk).reverse();
+        let complement = Simd::gather_or_default(&COMPLEMENT, reversed.cast());
+
+        if complement.simd_eq(Simd::splat(0)).any() {
+            let chunk_start = sequence.len() - (i + 1) * LANES;
+            return Err(last_invalid_base(
+                &sequence[chunk_start..chunk_start + LANES],
+                chunk_start,
+            ));
+        }
+        complement.copy_to_slice(&mut output[i * LANES..(i + 1) * LANES]);
+    }
+
+    // The remainder is the start of the sequence, so it goes at the end of the output
+    let tail_start = output.len() - remainder.len();
+    for (out, &byte) in output[tail_start..].iter_mut().zip(remainder.iter().rev()) {
+        *out = COMPLEMENT[byte as usize];
+    }
+    if remainder.iter().any(|&byte| COMPLEMENT[byte as usize] == 0) {
+        return Err(last_invalid_base(remainder, 0));
+    }
+
+    Ok(output)
+}
+
+/// The last invalid base of `bases`, matching the scan order of [`reverse_complement_sequence`]
+fn last_invalid_base(bases: &[u8], offset: usize) -> BaseError {
+    let (position, &byte) = bases
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|&(_, &byte)| COMPLEMENT[byte as usize] == 0)
+        .expect("the caller found an invalid base");
+
+    BaseError::InvalidBase {
+        position: offset + position,
+        byte,
+    }
+}
+
+/// Shannon entropy of the base composition, in bits per base
+///
+/// Ranges from 0.0 for a homopolymer run to 2.0 when A, C, G and T are
+/// equally frequent. Soft-masked (lowercase) bases count like uppercase ones,
+/// and anything else (N, line endings) is ignored.
+pub fn shannon_entropy(sequence: &[u8]) -> f64 {
+    let mut counts = [0usize; 4];
+    for &byte in sequence {
+        if let Some(index) = base_index(byte) {
+            counts[index] += 1;
+        }
+    }
+
+    entropy_of_counts(&counts)
+}
+
+/// [`shannon_entropy`] of every `window`-byte window, sliding one byte at a time
+///
+/// Low values point at low-complexity stretches such as repeats. Counts are
+/// updated incrementally, so this is linear in the sequence length. Empty if
+/// `window` is 0 or longer than the sequence.
+pub fn entropy_windows(sequence: &[u8], window: usize) -> Vec<f64> {
+    if window == 0 || window > sequence.len() {
+        return Vec::new();
+    }
+
+    let mut counts = [0usize; 4];
+    for &byte in &sequence[..window] {
+        if let Some(index) = base_index(byte) {
+            counts[index] += 1;
+        }
+    }
+
+    let mut entropies = Vec::with_capacity(sequence.len() - window + 1);
+    entropies.push(entropy_of_counts(&counts));
+
+    for (&leaving, &entering) in sequence.iter().zip(&sequence[window..]) {
+        if let Some(index) = base_index(leaving) {
+            counts[index] -= 1;
+        }
+        if let Some(index) = base_index(entering) {
+            counts[index] += 1;
+        }
+        entropies.push(entropy_of_counts(&counts));
+    }
+
+    entropies
+}
+
+/// GC skew `(G - C) / (G + C)` of every `window`-byte window, moving `step` bytes at a time
+///
+/// The skew changes sign around the replication origin and terminus of
+/// bacterial genomes. Soft-masked bases count like uppercase ones, and a
+/// window without any G or C has a skew of 0.0. Counts are updated
+/// incrementally when windows overlap. Empty if `window` or `step` is 0, or
+/// `window` is longer than the sequence.
+pub fn gc_skew_windows(sequence: &[u8], window: usize, step: usize) -> Vec<f64> {
+    if window == 0 || step == 0 || window > sequence.len() {
+        return Vec::new();
+    }
+
+    // (G, C) counts of a slice
+    let count = |bytes: &[u8]| {
+        bytes.iter().fold((0i64, 0i64), |(g, c), &byte| {
+            match byte.to_ascii_uppercase() {
+                b'G' => (g + 1, c),
+                b'C' => (g, c + 1),
+                _ => (g, c),
+            }
+        })
+    };
+    let skew = |(g, c): (i64, i64)| {
+        if g + c == 0 {
+            0.0
+        } else {
+            (g - c) as f64 / (g + c) as f64
+        }
+    };
+
+    let mut counts = count(&sequence[..window]);
+    let mut skews = vec![skew(counts)];
+
+    let mut start = step;
+    while start + window <= sequence.len() {
+        counts = if step < window {
+            // Drop the bytes leaving the window and add the ones entering it
+            let (left_g, left_c) = count(&sequence[start - step..start]);
+            let (entered_g, entered_c) = count(&sequence[start + window - step..start + window]);
+            (counts.0 - left_g + entered_g, counts.1 - left_c + entered_c)
+        } else {
+            count(&sequence[start..start + window])
+        };
+        skews.push(skew(counts));
+        start += step;
+    }
+
+    skews
+}
+
+/// Number of A, C, G and T in the sequence, in that order
+///
+/// Only uppercase bases are counted: soft-masked bases, N and line endings
+/// are ignored.
+pub fn count_bases(sequence: &[u8]) -> [u64; 4] {
+    let mut counts = [0u64; 4];
+    for &byte in sequence {
+        match byte {
+            b'A' => counts[0] += 1,
+            b'C' => counts[1] += 1,
+            b'G' => counts[2] += 1,
+            b'T' => counts[3] += 1,
+            _ => {}
+        }
+    }
+    counts
+}
+
+/// Same as [`count_bases`], 32 bytes at a time
+///
+/// The scalar version increments one entry of `counts` per byte, so
+/// consecutive bytes of the same base wait on each other. Here every base has
+/// its own vector of per-lane `u8` counters, one lane per byte position,
+/// incremented by comparing the chunk against that base. Bytes that aren't
+/// uppercase bases (line endings included) match none of the 4 vectors, so
+/// the comparison masks them out. The lane counters are folded into the
+/// totals every 255 chunks, before they can overflow.
+pub fn count_bases_simd(sequence: &[u8]) -> [u64; 4] {
+    const LANES: usize = 32;
+
+    let bases = BASES.map(Simd::<u8, LANES>::splat);
+    let (ones, zeros) = (Simd::splat(1), Simd::splat(0));
+    let mut counts = [0u64; 4];
+
+    let blocks = sequence.chunks(255 * LANES);
+    for block in blocks {
+        let mut partial = [Simd::<u8, LANES>::splat(0); 4];
+
+        let chunks = block.chunks_exact(LANES);
+        let remainder = chunks.remainder();
+
+        for chunk in chunks {
+            let bytes = Simd::from_slice(chunk);
+            for (partial, base) in partial.iter_mut().zip(&bases) {
+                *partial += bytes.simd_eq(*base).select(ones, zeros);
+            }
+        }
+
+        for (count, partial) in counts.iter_mut().zip(partial) {
+            *count += partial.cast::<u16>().reduce_sum() as u64;
+        }
+        for (count, tail_count) in counts.iter_mut().zip(count_bases(remainder)) {
+            *count += tail_count;
+        }
+    }
+
+    counts
+}
+
+/// Whether `sequence` has at least as many of each byte as `pattern`
+///
+/// A cheap necessary condition for `pattern` to occur in `sequence`: a record
+/// without any G can't contain a pattern with a G, whatever the order of its
+/// bases. Stops as soon as every byte of the pattern is accounted for.
+pub fn can_contain(sequence: &[u8], pattern: &[u8]) -> bool {
+    let mut needed = [0usize; 256];
+    for &byte in pattern {
+        needed[byte as usize] += 1;
+    }
+
+    let mut remaining = pattern.len();
+    for &byte in sequence {
+        if remaining == 0 {
+            break;
+        }
+        if needed[byte as usize] > 0 {
+            needed[byte as usize] -= 1;
+            remaining -= 1;
+        }
+    }
+
+    remaining == 0
+}
+
+/// Index of a base in [`BASES`], ignoring case
+fn base_index(byte: u8) -> Option<usize> {
+    BASES
+        .iter()
+        .position(|&base| base == byte.to_ascii_uppercase())
+}
+
+fn entropy_of_counts(counts: &[usize; 4]) -> f64 {
+    let total: usize = counts.iter().sum();
+
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_round_trip() {
+        let sequence = b"ACGTACGT";
+        let packed = pack_2bit(sequence).unwrap();
+
+        assert_eq!(packed, vec![0b00_01_10_11, 0b00_01_10_11]);
+        assert_eq!(unpack_2bit(&packed, sequence.len()), sequence);
+    }
+
+    #[test]
+    fn test_pack_round_trip_partial_byte() {
+        let sequence = b"GATTACA";
+        let packed = pack_2bit(sequence).unwrap();
+
+        assert_eq!(packed.len(), 2);
+        assert_eq!(unpack_2bit(&packed, sequence.len()), sequence);
+    }
+
+    #[test]
+    fn test_reverse_complement_round_trip() {
+        let sequence = b"ACGTTGCANNGATTACAGGGCCCATTTAAACGNNA";
+
+        let reverse_complement = reverse_complement_sequence(sequence).unwrap();
+        assert_eq!(&reverse_complement[..6], b"TNNCGT");
+        assert_eq!(
+            reverse_complement_sequence(&reverse_complement).unwrap(),
+            sequence
+        );
+
+        // 35 bases: two SIMD chunks and a 3-base tail
+        assert_eq!(
+            reverse_complement_simd(sequence).unwrap(),
+            reverse_complement
+        );
+        assert_eq!(
+            reverse_complement_simd(&reverse_complement).unwrap(),
+            sequence
+        );
+    }
+
+    #[test]
+    fn test_reverse_complement_rejects_invalid_base() {
+        let expected = Err(BaseError::InvalidBase {
+            position: 4,
+            byte: b'X',
+        });
+        assert_eq!(reverse_complement_sequence(b"ACGTXACGT"), expected);
+        assert_eq!(reverse_complement_simd(b"ACGTXACGT"), expected);
+
+        // Invalid bytes in a SIMD chunk and in the tail
+        let mut long = vec![b'A'; 40];
+        long[30] = b'u';
+        assert_eq!(
+            reverse_complement_simd(&long),
+            Err(BaseError::InvalidBase {
+                position: 30,
+                byte: b'u'
+            })
+        );
+        long[30] = b'A';
+        long[2] = b'-';
+        assert_eq!(
+            reverse_complement_simd(&long),
+            Err(BaseError::InvalidBase {
+                position: 2,
+                byte: b'-'
+            })
+        );
+    }
+
+    #[test]
+    fn test_pack_rejects_unknown_base() {
+        let result = pack_2bit(b"ACGNT");
+
+        assert_eq!(
+            result,
+            Err(PackError::InvalidBase {
+                position: 3,
+                byte: b'N'
+            })
+        );
+    }
+
+    #[test]
+    fn test_shannon_entropy() {
+        assert_eq!(shannon_entropy(b"AAAAAAAAAA"), 0.0);
+        assert_eq!(shannon_entropy(b"ACGTACGTacgt"), 2.0);
+        // Two bases, equally frequent, with an N and a line ending ignored
+        assert_eq!(shannon_entropy(b"ATATN\nATAT"), 1.0);
+        assert_eq!(shannon_entropy(b""), 0.0);
+        assert_eq!(shannon_entropy(b"NNNN"), 0.0);
+    }
+
+    #[test]
+    fn test_entropy_windows() {
+        // A low-complexity run between two mixed stretches
+        let sequence = b"ACGTAAAAAAAACGT";
+        let entropies = entropy_windows(sequence, 4);
+
+        assert_eq!(entropies.len(), sequence.len() - 3);
+        for (i, &entropy) in entropies.iter().enumerate() {
+            assert_eq!(entropy, shannon_entropy(&sequence[i..i + 4]), "Window {i}");
+        }
+        assert_eq!(entropies[0], 2.0);
+        assert_eq!(entropies[5], 0.0);
+
+        assert!(entropy_windows(sequence, 0).is_empty());
+        assert!(entropy_windows(sequence, 16).is_empty());
+        assert_eq!(
+            entropy_windows(sequence, 15),
+            vec![shannon_entropy(sequence)]
+        );
+    }
+
+    #[test]
+    fn test_gc_skew_windows() {
+        // A G-rich, a C-rich and a GC-free stretch of 8 bases each
+        let sequence = b"GGGgGGGCCCCcCCCGAAAAAAAA";
+
+        assert_eq!(gc_skew_windows(sequence, 8, 8), vec![0.75, -0.75, 0.0]);
+        // GGGC|CCCC has 3 G and 5 C, CCCG|AAAA has 1 G and 3 C
+        assert_eq!(
+            gc_skew_windows(sequence, 8, 4),
+            vec![0.75, -0.25, -0.75, -0.5, 0.0]
+        );
+
+        // Incremental updates agree with counting every window from scratch
+        for step in 1..=10 {
+            let expected: Vec<f64> = sequence
+                .windows(5)
+                .step_by(step)
+                .map(|window| gc_skew_windows(window, 5, 1)[0])
+                .collect();
+            assert_eq!(gc_skew_windows(sequence, 5, step), expected, "Step {step}");
+        }
+
+        assert!(gc_skew_windows(sequence, 0, 1).is_empty());
+        assert!(gc_skew_windows(sequence, 8, 0).is_empty());
+        assert!(gc_skew_windows(sequence, 25, 1).is_empty());
+    }
+
+    #[test]
+    fn test_can_contain() {
+        assert!(can_contain(b"ACGTACGT", b"GATC"));
+        // Right bases, wrong order: still a candidate
+        assert!(can_contain(b"TTGCA", b"ACGTT"));
+        assert!(!can_contain(b"ACATTACA", b"AG"));
+        // Not enough A
+        assert!(!can_contain(b"ACGT", b"AAC"));
+        assert!(can_contain(b"", b""));
+    }
+
+    #[test]
+    fn test_count_bases_simd_matches_scalar() {
+        let line = b"ACGTTGCAAAGGCTNNacgtACGGTTAACCGGTATATCGCGATCGATCGAAAACCCGGGTTT\n";
+        assert_eq!(count_bases(line), [15, 13, 15, 13]);
+        assert_eq!(count_bases_simd(line), count_bases(line));
+
+        // Long enough to fold the lane counters several times
+        let sequence: Vec<u8> = line.iter().copied().cycle().take(100_003).collect();
+        assert_eq!(count_bases_simd(&sequence), count_bases(&sequence));
+        let homopolymer = vec![b'G'; 255 * 32 * 2 + 5];
+        assert_eq!(count_bases_simd(&homopolymer), [0, 0, 16_325, 0]);
+
+        assert_eq!(count_bases_simd(b""), [0; 4]);
+    }
+}
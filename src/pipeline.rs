@@ -0,0 +1,149 @@
+/// Filter pipelines assembled at runtime
+///
+/// Every filter implements [`ImageFilter`], so a list of filters read from a
+/// config file can be stored as trait objects in a [`FilterChain`] and applied
+/// one after the other.
+use image::RgbImage;
+
+use crate::color_filters::adjust_saturation;
+use crate::lut_filters::{apply_brightness_contrast, apply_gamma};
+use crate::simd_brightness::brightness_simd;
+
+/// A filter producing a new image from an input image
+pub trait ImageFilter: std::fmt::Debug + Send + Sync {
+    fn apply(&self, img: &RgbImage) -> RgbImage;
+}
+
+/// Add a constant to every channel, see [`brightness_simd`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Brightness(pub i16);
+
+/// Scale channels away from mid-gray, see [`apply_brightness_contrast`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contrast(pub f32);
+
+/// Gamma correction, see [`apply_gamma`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gamma(pub f32);
+
+/// Scale the saturation, see [`adjust_saturation`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Saturation(pub f32);
+
+/// Replace every channel `c` with `255 - c`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Invert;
+
+impl ImageFilter for Brightness {
+    fn apply(&self, img: &RgbImage) -> RgbImage {
+        brightness_simd(img, self.0)
+    }
+}
+
+impl ImageFilter for Contrast {
+    fn apply(&self, img: &RgbImage) -> RgbImage {
+        apply_brightness_contrast(img, 0, self.0)
+    }
+}
+
+impl ImageFilter for Gamma {
+    fn apply(&self, img: &RgbImage) -> RgbImage {
+        apply_gamma(img, self.0)
+    }
+}
+
+impl ImageFilter for Saturation {
+    fn apply(&self, img: &RgbImage) -> RgbImage {
+        adjust_saturation(img, self.0)
+    }
+}
+
+impl ImageFilter for Invert {
+    fn apply(&self, img: &RgbImage) -> RgbImage {
+        let mut output = img.clone();
+        for channel in output.iter_mut() {
+            *channel = 255 - *channel;
+        }
+        output
+    }
+}
+
+/// Filters applied in order, the output of each one feeding the next
+///
+/// A chain is itself a filter, so chains can be nested.
+#[derive(Debug, Default)]
+pub struct FilterChain(pub Vec<Box<dyn ImageFilter>>);
+
+impl FilterChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a filter at the end of the chain
+    pub fn then(mut self, filter: impl ImageFilter + 'static) -> Self {
+        self.0.push(Box::new(filter));
+        self
+    }
+
+    /// The filters of the chain, in application order
+    pub fn filters(&self) -> &[Box<dyn ImageFilter>] {
+        &self.0
+    }
+}
+
+impl ImageFilter for FilterChain {
+    fn apply(&self, img: &RgbImage) -> RgbImage {
+        let Some((first, rest)) = self.0.split_first() else {
+            return img.clone();
+        };
+
+        rest.iter()
+            .fold(first.apply(img), |current, filter| filter.apply(&current))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+
+    fn create_test_image() -> RgbImage {
+        ImageBuffer::from_fn(6, 4, |x, y| Rgb([(x * 40) as u8, (y * 60) as u8, 90]))
+    }
+
+    #[test]
+    fn test_filter_chain_matches_sequential() {
+        let img = create_test_image();
+        let chain = FilterChain::new()
+            .then(Brightness(20))
+            .then(Contrast(0.5))
+            .then(Gamma(2.2));
+
+        let expected = apply_gamma(
+            &apply_brightness_contrast(&brightness_simd(&img, 20), 0, 0.5),
+            2.2,
+        );
+
+        assert_eq!(chain.apply(&img), expected);
+        assert_eq!(chain.filters().len(), 3);
+    }
+
+    #[test]
+    fn test_filter_chain_empty_and_nested() {
+        let img = create_test_image();
+
+        assert_eq!(FilterChain::new().apply(&img), img);
+
+        let double_invert = FilterChain::new()
+            .then(Invert)
+            .then(FilterChain::new().then(Invert));
+        assert_eq!(double_invert.apply(&img), img);
+    }
+
+    #[test]
+    fn test_invert() {
+        let img = ImageBuffer::from_pixel(1, 1, Rgb([0u8, 100, 255]));
+
+        assert_eq!(Invert.apply(&img).get_pixel(0, 0), &Rgb([255, 155, 0]));
+    }
+}
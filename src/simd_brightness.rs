@@ -19,6 +19,8 @@ use std::simd::{
 /// Perfect example: Brightness adjustment (add constant to each pixel)
 use image::{ImageBuffer, Rgb, RgbImage};
 
+use crate::helpers::build_rgb;
+
 /// Naive scalar implementation: Process one pixel at a time
 pub fn brightness_scalar(img: &RgbImage, adjustment: i16) -> RgbImage {
     let (width, height) = img.dimensions();
@@ -54,7 +56,39 @@ pub fn brightness_autovec(img: &RgbImage, adjustment: i16) -> RgbImage {
         output[i] = value.clamp(0, 255) as u8;
     }
 
-    ImageBuffer::from_raw(width, height, output).unwrap()
+    build_rgb(width, height, output).expect("output buffer is sized from the input image")
+}
+
+/// Channel values saturated by a brightness adjustment, see [`brightness_with_stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClipStats {
+    /// Values that would have gone below 0
+    pub clipped_low: u64,
+    /// Values that would have gone above 255
+    pub clipped_high: u64,
+}
+
+/// Same as [`brightness_autovec`], also counting the channel values that got clipped
+///
+/// Only values pushed past 0 or 255 count, one already at 255 that stays
+/// there lost nothing. Lots of clipping means the adjustment was too strong.
+pub fn brightness_with_stats(img: &RgbImage, adjustment: i16) -> (RgbImage, ClipStats) {
+    let (width, height) = img.dimensions();
+
+    let input = img.as_raw();
+    let mut output = vec![0u8; input.len()];
+    let mut stats = ClipStats::default();
+
+    for (out, &value) in output.iter_mut().zip(input) {
+        let value = value as i16 + adjustment;
+        stats.clipped_low += (value < 0) as u64;
+        stats.clipped_high += (value > 255) as u64;
+        *out = value.clamp(0, 255) as u8;
+    }
+
+    let output =
+        build_rgb(width, height, output).expect("output buffer is sized from the input image");
+    (output, stats)
 }
 
 /// Explicit SIMD using std::simd (portable_simd)
@@ -67,25 +101,63 @@ pub fn brightness_autovec(img: &RgbImage, adjustment: i16) -> RgbImage {
 ///
 /// Note: Requires nightly Rust for now
 pub fn brightness_simd(img: &RgbImage, adjustment: i16) -> RgbImage {
-    use std::simd::{Simd, i16x16, u8x16};
+    let (width, height) = img.dimensions();
+
+    let input = img.as_raw();
+    let mut output = vec![0u8; input.len()];
+    brightness_simd_into(input, &mut output, adjustment);
+
+    build_rgb(width, height, output).expect("output buffer is sized from the input image")
+}
 
+/// Same as [`brightness_simd`], writing into a caller-provided buffer
+///
+/// Works on raw channel bytes, so a video loop can allocate `output` once and
+/// reuse it for every frame. `input` and `output` must have the same length.
+pub fn brightness_simd_into(input: &[u8], output: &mut [u8], adjustment: i16) {
+    brightness_simd_generic_into::<16>(input, output, adjustment);
+}
+
+/// Explicit SIMD with a configurable number of lanes
+///
+/// Same algorithm as [`brightness_simd`], but processing `LANES` bytes per
+/// iteration: 16 fits an SSE register, 32 an AVX2 one and 64 an AVX-512 one.
+pub fn brightness_simd_generic<const LANES: usize>(img: &RgbImage, adjustment: i16) -> RgbImage {
     let (width, height) = img.dimensions();
 
     let input = img.as_raw();
     let mut output = vec![0u8; input.len()];
+    brightness_simd_generic_into::<LANES>(input, &mut output, adjustment);
 
-    let adjust_vec = Simd::splat(adjustment);
+    build_rgb(width, height, output).expect("output buffer is sized from the input image")
+}
 
-    // Process 16 bytes at a time
-    let chunks = input.chunks_exact(16);
+/// Same as [`brightness_simd_into`], processing `LANES` bytes at a time
+fn brightness_simd_generic_into<const LANES: usize>(
+    input: &[u8],
+    output: &mut [u8],
+    adjustment: i16,
+) {
+    use std::simd::Simd;
+
+    assert_eq!(
+        input.len(),
+        output.len(),
+        "Input and output buffers must have the same length"
+    );
+
+    let adjust_vec = Simd::<i16, LANES>::splat(adjustment);
+
+    // Process LANES bytes at a time
+    let chunks = input.chunks_exact(LANES);
     let remainder = chunks.remainder();
 
     for (i, chunk) in chunks.enumerate() {
-        // Load 16 u8 values
-        let pixels = u8x16::from_slice(chunk);
+        // Load LANES u8 values
+        let pixels = Simd::<u8, LANES>::from_slice(chunk);
 
         // Convert to i16 for safe arithmetic
-        let pixels_i16: i16x16 = pixels.cast();
+        let pixels_i16: Simd<i16, LANES> = pixels.cast();
 
         // Add adjustment
         let adjusted = pixels_i16 + adjust_vec;
@@ -94,10 +166,10 @@ pub fn brightness_simd(img: &RgbImage, adjustment: i16) -> RgbImage {
         let clamped = adjusted.simd_clamp(Simd::splat(0), Simd::splat(255));
 
         // Convert back to u8
-        let result: u8x16 = clamped.cast();
+        let result: Simd<u8, LANES> = clamped.cast();
 
         // Store result
-        result.copy_to_slice(&mut output[i * 16..(i + 1) * 16]);
+        result.copy_to_slice(&mut output[i * LANES..(i + 1) * LANES]);
     }
 
     // Handle remaining bytes
@@ -105,8 +177,147 @@ pub fn brightness_simd(img: &RgbImage, adjustment: i16) -> RgbImage {
         let value = byte as i16 + adjustment;
         output[input.len() - remainder.len() + i] = value.clamp(0, 255) as u8;
     }
+}
+
+/// Scale then offset every channel in a single SIMD pass: `clamp(pixel * scale + offset)`
+///
+/// Bytes are widened to `f32` lanes, so the multiply and the add happen before
+/// a single rounding and clamping step. Doing it as two passes (scale, then
+/// brightness) reads and writes the whole image twice.
+pub fn linear_transform_simd(img: &RgbImage, scale: f32, offset: i16) -> RgbImage {
+    use std::simd::{Simd, StdFloat, f32x16, num::SimdFloat, u8x16};
+
+    let (width, height) = img.dimensions();
+
+    let input = img.as_raw();
+    let mut output = vec![0u8; input.len()];
+
+    let scale_vec = f32x16::splat(scale);
+    let offset_vec = f32x16::splat(offset as f32);
+
+    let chunks = input.chunks_exact(16);
+    let remainder = chunks.remainder();
+
+    for (i, chunk) in chunks.enumerate() {
+        let pixels: f32x16 = u8x16::from_slice(chunk).cast();
+
+        let transformed = pixels.mul_add(scale_vec, offset_vec).round();
+        let clamped = transformed.simd_clamp(Simd::splat(0.0), Simd::splat(255.0));
+
+        let result: u8x16 = clamped.cast();
+        result.copy_to_slice(&mut output[i * 16..(i + 1) * 16]);
+    }
+
+    // Handle remaining bytes
+    for (i, &byte) in remainder.iter().enumerate() {
+        let value = (byte as f32).mul_add(scale, offset as f32).round();
+        output[input.len() - remainder.len() + i] = value.clamp(0.0, 255.0) as u8;
+    }
+
+    build_rgb(width, height, output).expect("output buffer is sized from the input image")
+}
+
+/// Explicit SIMD with `i32` lanes for large adjustments
+///
+/// `brightness_simd` adds in `i16`, so an adjustment close to `i16::MAX` plus a
+/// pixel value wraps around before the clamp ever sees it. Here the adjustment
+/// is first limited to ±255 (beyond that every pixel saturates anyway) and the
+/// addition happens in `i32` lanes, so no input can overflow.
+pub fn brightness_simd_wide(img: &RgbImage, adjustment: i32) -> RgbImage {
+    use std::simd::{Simd, i32x16, u8x16};
+
+    let (width, height) = img.dimensions();
+
+    let input = img.as_raw();
+    let mut output = vec![0u8; input.len()];
+
+    // Anything outside -255..=255 already saturates every pixel
+    let adjustment = adjustment.clamp(-255, 255);
+    let adjust_vec = Simd::splat(adjustment);
+
+    // Process 16 bytes at a time
+    let chunks = input.chunks_exact(16);
+    let remainder = chunks.remainder();
+
+    for (i, chunk) in chunks.enumerate() {
+        let pixels = u8x16::from_slice(chunk);
+
+        // Widen to i32 so the addition can't overflow
+        let pixels_i32: i32x16 = pixels.cast();
+        let adjusted = pixels_i32 + adjust_vec;
+        let clamped = adjusted.simd_clamp(Simd::splat(0), Simd::splat(255));
+
+        let result: u8x16 = clamped.cast();
+        result.copy_to_slice(&mut output[i * 16..(i + 1) * 16]);
+    }
+
+    // Handle remaining bytes
+    for (i, &byte) in remainder.iter().enumerate() {
+        let value = byte as i32 + adjustment;
+        output[input.len() - remainder.len() + i] = value.clamp(0, 255) as u8;
+    }
+
+    build_rgb(width, height, output).expect("output buffer is sized from the input image")
+}
+
+/// Hand-written AVX2 intrinsics, as a contrast to the portable_simd versions
+///
+/// `_mm256_adds_epu8` and `_mm256_subs_epu8` saturate at 0 and 255 on their
+/// own, so bytes never need to be widened to `i16`: 32 channels are processed
+/// per instruction instead of 16. The price is portability, this only exists
+/// on x86_64 and falls back to [`brightness_simd`] when the CPU lacks AVX2.
+///
+/// Matches [`brightness_simd`] for every adjustment that doesn't overflow its
+/// `i16` addition, i.e. up to `i16::MAX - 255` in absolute value.
+#[cfg(target_arch = "x86_64")]
+pub fn brightness_avx2(img: &RgbImage, adjustment: i16) -> RgbImage {
+    if !is_x86_feature_detected!("avx2") {
+        return brightness_simd(img, adjustment);
+    }
+
+    let (width, height) = img.dimensions();
+
+    let input = img.as_raw();
+    let mut output = vec![0u8; input.len()];
+    // SAFETY: AVX2 support was checked above
+    unsafe { brightness_avx2_into(input, &mut output, adjustment) };
+
+    build_rgb(width, height, output).expect("output buffer is sized from the input image")
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+fn brightness_avx2_into(input: &[u8], output: &mut [u8], adjustment: i16) {
+    use std::arch::x86_64::{
+        __m256i, _mm256_adds_epu8, _mm256_loadu_si256, _mm256_set1_epi8, _mm256_storeu_si256,
+        _mm256_subs_epu8,
+    };
+
+    // Anything outside -255..=255 already saturates every pixel
+    let magnitude = adjustment.unsigned_abs().min(255) as u8;
+    let adjust_vec = _mm256_set1_epi8(magnitude as i8);
+
+    let chunks = input.chunks_exact(32);
+    let remainder = chunks.remainder();
+
+    for (chunk, out) in chunks.zip(output.chunks_exact_mut(32)) {
+        // SAFETY: both chunks are exactly 32 bytes, and loadu/storeu don't need alignment
+        unsafe {
+            let pixels = _mm256_loadu_si256(chunk.as_ptr().cast::<__m256i>());
+            let result = if adjustment >= 0 {
+                _mm256_adds_epu8(pixels, adjust_vec)
+            } else {
+                _mm256_subs_epu8(pixels, adjust_vec)
+            };
+            _mm256_storeu_si256(out.as_mut_ptr().cast::<__m256i>(), result);
+        }
+    }
 
-    ImageBuffer::from_raw(width, height, output).unwrap()
+    // Handle remaining bytes
+    for (i, &byte) in remainder.iter().enumerate() {
+        let value = byte as i16 + adjustment;
+        output[input.len() - remainder.len() + i] = value.clamp(0, 255) as u8;
+    }
 }
 
 #[cfg(test)]
@@ -178,4 +389,147 @@ mod tests {
         // Both should produce identical results
         assert_eq!(scalar.as_raw(), autovec.as_raw());
     }
+
+    #[test]
+    fn test_brightness_simd_into_reuses_buffer() {
+        let first = create_test_image();
+        let second =
+            ImageBuffer::from_fn(4, 4, |x, y| Rgb([255 - (x * 40) as u8, 7, (y * 60) as u8]));
+
+        let mut output = vec![0u8; first.as_raw().len()];
+
+        brightness_simd_into(first.as_raw(), &mut output, 30);
+        assert_eq!(output, *brightness_simd(&first, 30).as_raw());
+
+        brightness_simd_into(second.as_raw(), &mut output, -50);
+        assert_eq!(output, *brightness_simd(&second, -50).as_raw());
+    }
+
+    #[test]
+    #[should_panic(expected = "same length")]
+    fn test_brightness_simd_into_length_mismatch() {
+        let mut output = vec![0u8; 10];
+        brightness_simd_into(&[0u8; 12], &mut output, 10);
+    }
+
+    #[test]
+    fn test_brightness_simd_generic_matches_simd() {
+        // 7x5x3 = 105 bytes: a tail for every lane count
+        let img = ImageBuffer::from_fn(7, 5, |x, y| Rgb([(x * 40) as u8, (y * 60) as u8, 250]));
+
+        for adjustment in [-100, 0, 30, 200] {
+            let expected = brightness_simd(&img, adjustment);
+
+            assert_eq!(brightness_simd_generic::<16>(&img, adjustment), expected);
+            assert_eq!(brightness_simd_generic::<32>(&img, adjustment), expected);
+            assert_eq!(brightness_simd_generic::<64>(&img, adjustment), expected);
+        }
+    }
+
+    #[test]
+    fn test_linear_transform_matches_scale_then_brightness() {
+        // 7x5x3 = 105 bytes: six SIMD chunks and a 9-byte tail
+        let img = ImageBuffer::from_fn(7, 5, |x, y| Rgb([(x * 30) as u8, (y * 45) as u8, 77]));
+
+        for (scale, offset) in [(1.0, 0), (0.5, 40), (1.2, -20), (0.8, 10)] {
+            // Scale first, then brighten the scaled image
+            let mut scaled = img.clone();
+            for channel in scaled.iter_mut() {
+                *channel = (*channel as f32 * scale).round().clamp(0.0, 255.0) as u8;
+            }
+            let expected = brightness_scalar(&scaled, offset);
+
+            assert_eq!(
+                linear_transform_simd(&img, scale, offset),
+                expected,
+                "scale {scale}, offset {offset}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_linear_transform_clamps() {
+        let img = ImageBuffer::from_fn(6, 3, |x, _| Rgb([x as u8 * 50, 0, 255]));
+
+        let result = linear_transform_simd(&img, 2.0, 10);
+
+        assert_eq!(result.get_pixel(0, 0), &Rgb([10, 10, 255]));
+        assert_eq!(result.get_pixel(5, 2), &Rgb([255, 10, 255]));
+        let result = linear_transform_simd(&img, 0.5, -200);
+        assert!(result.iter().all(|&c| c == 0));
+    }
+
+    #[test]
+    fn test_brightness_simd_wide_matches_simd() {
+        let img = create_test_image();
+
+        for adjustment in [-300, -40, 0, 40, 300] {
+            let wide = brightness_simd_wide(&img, adjustment);
+            let narrow = brightness_simd(&img, adjustment as i16);
+
+            assert_eq!(wide.as_raw(), narrow.as_raw());
+        }
+    }
+
+    #[test]
+    fn test_brightness_simd_wide_large_adjustment_saturates() {
+        // 5x5 so both the SIMD body and the scalar remainder are exercised
+        let img = ImageBuffer::from_fn(5, 5, |x, y| Rgb([(x * 50) as u8, (y * 50) as u8, 255]));
+
+        let result = brightness_simd_wide(&img, 40000);
+        assert!(result.as_raw().iter().all(|&value| value == 255));
+
+        let result = brightness_simd_wide(&img, -40000);
+        assert!(result.as_raw().iter().all(|&value| value == 0));
+    }
+
+    /// Gated on AVX2 at runtime, like `brightness_avx2` itself
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_brightness_avx2_matches_simd() {
+        if !is_x86_feature_detected!("avx2") {
+            eprintln!("skipping test_brightness_avx2_matches_simd: AVX2 is not available");
+            return;
+        }
+
+        // 37 x 3 pixels: 333 bytes, so the scalar tail is exercised too
+        let img = ImageBuffer::from_fn(37, 3, |x, y| {
+            Rgb([(x * 7) as u8, (y * 90 + x) as u8, (255 - x * 3) as u8])
+        });
+
+        for adjustment in [-1000, -255, -128, -30, -1, 0, 1, 30, 127, 255, 1000] {
+            assert_eq!(
+                brightness_avx2(&img, adjustment),
+                brightness_simd(&img, adjustment),
+                "Adjustment {adjustment}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_brightness_with_stats() {
+        let img = create_test_image();
+
+        // Red and green are 0, 50, 100 or 150, blue is 128: red and green
+        // clip from 100 on, blue everywhere
+        let (result, stats) = brightness_with_stats(&img, 200);
+        assert_eq!(result.as_raw(), brightness_scalar(&img, 200).as_raw());
+        assert_eq!(
+            stats,
+            ClipStats {
+                clipped_low: 0,
+                clipped_high: 8 + 8 + 16
+            }
+        );
+
+        // 100 - 100 lands exactly on 0 without clipping
+        let (_, stats) = brightness_with_stats(&img, -100);
+        assert_eq!(
+            stats,
+            ClipStats {
+                clipped_low: 8 + 8,
+                clipped_high: 0
+            }
+        );
+    }
 }
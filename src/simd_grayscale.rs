@@ -0,0 +1,249 @@
+use std::simd::cmp::SimdPartialOrd;
+use std::simd::num::SimdUint;
+use std::simd::{Select, Simd, simd_swizzle, u8x16, u16x16};
+
+/// SIMD Challenge: RGB to grayscale without a scalar deinterleave
+///
+/// RGB pixels are stored interleaved (`RGBRGBRGB...`), but SIMD wants all the
+/// reds in one vector, all the greens in another, and so on. Instead of
+/// splitting channels one byte at a time, we load 16 pixels (48 bytes) at once
+/// and use `simd_swizzle!` to gather every third byte into its own vector.
+///
+/// The luminosity weights are applied in 8-bit fixed point:
+/// Gray = (77 * R + 150 * G + 29 * B) >> 8
+/// which is within 1 of the floating-point result for every input.
+use image::{GrayImage, RgbImage};
+
+use crate::helpers::build_image;
+
+/// Fixed-point weights (out of 256) for 0.299, 0.587 and 0.114
+const RED_WEIGHT: u16 = 77;
+const GREEN_WEIGHT: u16 = 150;
+const BLUE_WEIGHT: u16 = 29;
+
+/// Convert RGB to grayscale, 16 pixels at a time
+pub fn rgb_to_gray_simd_swizzle(img: &RgbImage) -> GrayImage {
+    let (width, height) = img.dimensions();
+
+    let input = img.as_raw();
+    let mut output = vec![0u8; input.len() / 3];
+    rgb_to_gray_simd_into(input, &mut output);
+
+    build_image(width, height, output).expect("output buffer is sized from the input image")
+}
+
+/// Same as [`rgb_to_gray_simd_swizzle`], writing into a caller-provided buffer
+///
+/// Works on raw interleaved bytes, so a video loop can allocate `output` once
+/// and reuse it for every frame, with no allocation at all per frame.
+/// `input` must hold exactly 3 bytes per byte of `output`.
+pub fn rgb_to_gray_simd_into(input: &[u8], output: &mut [u8]) {
+    assert_eq!(
+        input.len(),
+        3 * output.len(),
+        "Input must hold exactly 3 bytes per output pixel"
+    );
+
+    // Process 16 pixels (48 bytes) at a time
+    let chunks = input.chunks_exact(48);
+    let remainder = chunks.remainder();
+
+    for (i, chunk) in chunks.enumerate() {
+        // 48 bytes in a 64-lane vector, the last 16 lanes are unused. While
+        // at least 64 bytes are left, a plain load is cheaper than a padded one
+        let start = i * 48;
+        let pixels = match input.get(start..start + 64) {
+            Some(bytes) => Simd::<u8, 64>::from_slice(bytes),
+            None => Simd::<u8, 64>::load_or_default(chunk),
+        };
+
+        // Gather every third byte starting at 0, 1 and 2
+        let r: u8x16 = simd_swizzle!(
+            pixels,
+            [0, 3, 6, 9, 12, 15, 18, 21, 24, 27, 30, 33, 36, 39, 42, 45]
+        );
+        let g: u8x16 = simd_swizzle!(
+            pixels,
+            [1, 4, 7, 10, 13, 16, 19, 22, 25, 28, 31, 34, 37, 40, 43, 46]
+        );
+        let b: u8x16 = simd_swizzle!(
+            pixels,
+            [2, 5, 8, 11, 14, 17, 20, 23, 26, 29, 32, 35, 38, 41, 44, 47]
+        );
+
+        // Widen to u16 so the weighted sum (at most 255 * 256) fits
+        let r: u16x16 = r.cast();
+        let g: u16x16 = g.cast();
+        let b: u16x16 = b.cast();
+
+        let gray = (r * Simd::splat(RED_WEIGHT)
+            + g * Simd::splat(GREEN_WEIGHT)
+            + b * Simd::splat(BLUE_WEIGHT))
+            >> Simd::splat(8);
+
+        let gray: u8x16 = gray.cast();
+        gray.copy_to_slice(&mut output[i * 16..(i + 1) * 16]);
+    }
+
+    // Handle remaining pixels
+    let tail_start = output.len() - remainder.len() / 3;
+    for (i, pixel) in remainder.chunks_exact(3).enumerate() {
+        output[tail_start + i] = gray_fixed_point(pixel[0], pixel[1], pixel[2]);
+    }
+}
+
+fn gray_fixed_point(r: u8, g: u8, b: u8) -> u8 {
+    ((r as u16 * RED_WEIGHT + g as u16 * GREEN_WEIGHT + b as u16 * BLUE_WEIGHT) >> 8) as u8
+}
+
+/// Binarize a grayscale image: 255 where the value is at least `level`, 0 elsewhere
+///
+/// Scalar version, through a 256-entry lookup table.
+pub fn threshold(img: &GrayImage, level: u8) -> GrayImage {
+    let lut: [u8; 256] = std::array::from_fn(|value| if value >= level as usize { 255 } else { 0 });
+    let (width, height) = img.dimensions();
+
+    let output = img
+        .as_raw()
+        .iter()
+        .map(|&value| lut[value as usize])
+        .collect();
+
+    build_image(width, height, output).expect("output buffer is sized from the input image")
+}
+
+/// Same as [`threshold`], 32 pixels at a time
+///
+/// `simd_ge` against the splatted `level` gives a mask, which selects between
+/// a vector of 255 and a vector of 0. Full groups use plain loads and stores,
+/// which keeps the loop several times cheaper than masked ones: only the last
+/// partial group is loaded zero-padded, and only its valid lanes are stored.
+pub fn threshold_simd(img: &GrayImage, level: u8) -> GrayImage {
+    const LANES: usize = 32;
+    let (width, height) = img.dimensions();
+
+    let input = img.as_raw();
+    let mut output = vec![0u8; input.len()];
+
+    let level = Simd::<u8, LANES>::splat(level);
+    let white = Simd::splat(255);
+    let black = Simd::splat(0);
+
+    let mut in_chunks = input.chunks_exact(LANES);
+    let mut out_chunks = output.chunks_exact_mut(LANES);
+    for (in_chunk, out_chunk) in (&mut in_chunks).zip(&mut out_chunks) {
+        let values = Simd::<u8, LANES>::from_slice(in_chunk);
+        values
+            .simd_ge(level)
+            .select(white, black)
+            .copy_to_slice(out_chunk);
+    }
+
+    let (in_tail, out_tail) = (in_chunks.remainder(), out_chunks.into_remainder());
+    let values = Simd::<u8, LANES>::load_or_default(in_tail);
+    let binary = values.simd_ge(level).select(white, black);
+    out_tail.copy_from_slice(&binary.as_array()[..out_tail.len()]);
+
+    build_image(width, height, output).expect("output buffer is sized from the input image")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::helpers::assert_eq_gray_img;
+    use crate::lut_grayscale::{GrayscaleLut, rgb_to_gray_naive, rgb_to_gray_small_lut};
+
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+
+    #[test]
+    fn test_with_real_image() {
+        let img = image::open("data/small.jpg").unwrap().to_rgb8();
+        let naive = rgb_to_gray_naive(&img);
+        let simd = rgb_to_gray_simd_swizzle(&img);
+
+        assert_eq_gray_img(&naive, &simd);
+
+        simd.save("test_grayscale_simd_swizzle.png").unwrap();
+    }
+
+    #[test]
+    fn test_rgb_to_gray_simd_swizzle_matches_naive() {
+        // 7x5 = 35 pixels: two full SIMD chunks and a 3-pixel scalar tail
+        let img = ImageBuffer::from_fn(7, 5, |x, y| {
+            Rgb([(x * 37) as u8, (y * 61) as u8, ((x + y) * 23) as u8])
+        });
+        let naive = rgb_to_gray_naive(&img);
+        let simd = rgb_to_gray_simd_swizzle(&img);
+
+        assert_eq!(simd.dimensions(), (7, 5));
+        for (expected, actual) in naive.pixels().zip(simd.pixels()) {
+            assert!(
+                expected[0].abs_diff(actual[0]) <= 1,
+                "Expected {} got {}",
+                expected[0],
+                actual[0]
+            );
+        }
+    }
+
+    #[test]
+    fn test_rgb_to_gray_simd_swizzle_primaries() {
+        let img = ImageBuffer::from_fn(17, 1, |x, _| match x % 3 {
+            0 => Rgb([255u8, 0, 0]),
+            1 => Rgb([0u8, 255, 0]),
+            _ => Rgb([255u8, 255, 255]),
+        });
+        let gray = rgb_to_gray_simd_swizzle(&img);
+
+        assert_eq!(gray.get_pixel(0, 0)[0], 76); // Red
+        assert_eq!(gray.get_pixel(1, 0)[0], 149); // Green
+        assert_eq!(gray.get_pixel(2, 0)[0], 255); // White
+        assert_eq!(gray.get_pixel(16, 0)[0], 149); // Green, in the scalar tail
+    }
+
+    #[test]
+    fn test_threshold_simd_matches_scalar() {
+        // 13x5 = 65 pixels: two full SIMD groups and a 1-pixel tail
+        let img = ImageBuffer::from_fn(13, 5, |x, y| image::Luma([(x * 20 + y * 3) as u8]));
+
+        for level in [0, 1, 100, 128, 254, 255] {
+            let scalar = threshold(&img, level);
+            assert_eq!(scalar, threshold_simd(&img, level), "Level {level}");
+
+            for (value, binary) in img.pixels().zip(scalar.pixels()) {
+                let expected = if value[0] >= level { 255 } else { 0 };
+                assert_eq!(binary[0], expected, "Level {level}, value {}", value[0]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rgb_to_gray_simd_into_matches_small_lut() {
+        // 7x5 = 35 pixels: two full SIMD chunks and a 3-pixel scalar tail
+        let img = ImageBuffer::from_fn(7, 5, |x, y| {
+            Rgb([(x * 37) as u8, (y * 61) as u8, ((x + y) * 23) as u8])
+        });
+        let expected = rgb_to_gray_small_lut(&img, &GrayscaleLut::new());
+
+        // The same buffer is reused, and fully overwritten
+        let mut output = vec![0xAA; 35];
+        for _ in 0..2 {
+            rgb_to_gray_simd_into(img.as_raw(), &mut output);
+            for (expected, &actual) in expected.pixels().zip(&output) {
+                assert!(
+                    expected[0].abs_diff(actual) <= 1,
+                    "Expected {} got {actual}",
+                    expected[0]
+                );
+            }
+        }
+        assert_eq!(output, *rgb_to_gray_simd_swizzle(&img).as_raw());
+    }
+
+    #[test]
+    #[should_panic(expected = "3 bytes per output pixel")]
+    fn test_rgb_to_gray_simd_into_length_mismatch() {
+        let mut output = vec![0u8; 5];
+        rgb_to_gray_simd_into(&[0u8; 12], &mut output);
+    }
+}
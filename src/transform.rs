@@ -0,0 +1,300 @@
+/// Geometric transforms operating directly on the raw RGB buffer
+///
+/// Each pixel is a triple of bytes, and rows are stored one after another.
+/// Flips and rotations only move whole pixels (or whole rows) around, so no
+/// per-channel math is needed: it's all memory shuffling.
+use image::RgbImage;
+
+use crate::helpers::build_rgb;
+
+/// Mirror the image left-to-right by reversing the pixel triples of each row
+pub fn flip_horizontal(img: &RgbImage) -> RgbImage {
+    let (width, height) = img.dimensions();
+    if width == 0 || height == 0 {
+        // No rows to split the buffer into
+        return img.clone();
+    }
+    let row_len = width as usize * 3;
+
+    let mut output = img.as_raw().clone();
+
+    for row in output.chunks_exact_mut(row_len) {
+        // Reversing the bytes would also swap R and B, so reverse pixels instead
+        let (pixels, _) = row.as_chunks_mut::<3>();
+        pixels.reverse();
+    }
+
+    build_rgb(width, height, output).expect("output buffer is sized from the input image")
+}
+
+/// Mirror the image top-to-bottom by swapping whole rows
+pub fn flip_vertical(img: &RgbImage) -> RgbImage {
+    let (width, height) = img.dimensions();
+    if width == 0 || height == 0 {
+        // No rows to split the buffer into
+        return img.clone();
+    }
+    let row_len = width as usize * 3;
+
+    let input = img.as_raw();
+    let mut output = Vec::with_capacity(input.len());
+
+    for row in input.chunks_exact(row_len).rev() {
+        output.extend_from_slice(row);
+    }
+
+    build_rgb(width, height, output).expect("output buffer is sized from the input image")
+}
+
+/// Rotate the image 90 degrees clockwise
+///
+/// The output is `height x width`: input pixel (x, y) lands at (height - 1 - y, x).
+pub fn rotate90(img: &RgbImage) -> RgbImage {
+    let (width, height) = img.dimensions();
+    let (w, h) = (width as usize, height as usize);
+
+    let input = img.as_raw();
+    let mut output = vec![0u8; input.len()];
+
+    for y in 0..h {
+        for x in 0..w {
+            let src = (y * w + x) * 3;
+            let dst = (x * h + (h - 1 - y)) * 3;
+            output[dst..dst + 3].copy_from_slice(&input[src..src + 3]);
+        }
+    }
+
+    build_rgb(height, width, output).expect("output buffer is sized from the input image")
+}
+
+/// Rotate the image 180 degrees
+///
+/// This is the same as reversing the order of every pixel in the buffer.
+pub fn rotate180(img: &RgbImage) -> RgbImage {
+    let (width, height) = img.dimensions();
+
+    let mut output = img.as_raw().clone();
+    let (pixels, _) = output.as_chunks_mut::<3>();
+    pixels.reverse();
+
+    build_rgb(width, height, output).expect("output buffer is sized from the input image")
+}
+
+/// Rotate the image 270 degrees clockwise (90 degrees counter-clockwise)
+///
+/// The output is `height x width`: input pixel (x, y) lands at (y, width - 1 - x).
+pub fn rotate270(img: &RgbImage) -> RgbImage {
+    let (width, height) = img.dimensions();
+    let (w, h) = (width as usize, height as usize);
+
+    let input = img.as_raw();
+    let mut output = vec![0u8; input.len()];
+
+    for y in 0..h {
+        for x in 0..w {
+            let src = (y * w + x) * 3;
+            let dst = ((w - 1 - x) * h + y) * 3;
+            output[dst..dst + 3].copy_from_slice(&input[src..src + 3]);
+        }
+    }
+
+    build_rgb(height, width, output).expect("output buffer is sized from the input image")
+}
+
+/// Halve both dimensions, each output pixel averaging a 2x2 block of input pixels
+///
+/// Odd dimensions drop their last row or column, and a dimension of 1 stays 1
+/// (averaging 1x2 or 2x1 blocks). Averages are rounded to the nearest value.
+/// An empty image has no block to average, and is returned unchanged.
+pub fn downscale_box(img: &RgbImage) -> RgbImage {
+    let (width, height) = img.dimensions();
+    if width == 0 || height == 0 {
+        return img.clone();
+    }
+    let (out_width, out_height) = ((width / 2).max(1), (height / 2).max(1));
+    let input = img.as_raw();
+    let row_len = width as usize * 3;
+
+    let mut output = Vec::with_capacity(out_width as usize * out_height as usize * 3);
+
+    for out_y in 0..out_height as usize {
+        let rows = out_y * 2..(out_y * 2 + 2).min(height as usize);
+
+        for out_x in 0..out_width as usize {
+            let columns = out_x * 2..(out_x * 2 + 2).min(width as usize);
+            let count = (rows.len() * columns.len()) as u32;
+
+            for channel in 0..3 {
+                let sum: u32 = rows
+                    .clone()
+                    .flat_map(|y| columns.clone().map(move |x| y * row_len + x * 3 + channel))
+                    .map(|index| input[index] as u32)
+                    .sum();
+                output.push(((sum + count / 2) / count) as u8);
+            }
+        }
+    }
+
+    build_rgb(out_width, out_height, output).expect("output buffer is sized from the input image")
+}
+
+/// Mipmap pyramid: the image, then successive [`downscale_box`] halvings
+///
+/// Holds at most `levels` images, the first being a copy of `img`. Stops
+/// early once a level is 1 pixel wide or high, since it can't be halved.
+pub fn image_pyramid(img: &RgbImage, levels: usize) -> Vec<RgbImage> {
+    let mut pyramid: Vec<RgbImage> = Vec::with_capacity(levels);
+    if levels == 0 {
+        return pyramid;
+    }
+    pyramid.push(img.clone());
+
+    while pyramid.len() < levels {
+        let last = pyramid.last().unwrap();
+        if last.width() <= 1 || last.height() <= 1 {
+            break;
+        }
+        pyramid.push(downscale_box(last));
+    }
+
+    pyramid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+
+    fn create_test_image() -> RgbImage {
+        ImageBuffer::from_fn(3, 2, |x, y| Rgb([x as u8, y as u8, (x * 10 + y) as u8]))
+    }
+
+    #[test]
+    fn test_flip_horizontal() {
+        let img = create_test_image();
+        let result = flip_horizontal(&img);
+
+        assert_eq!(result.dimensions(), (3, 2));
+        assert_eq!(result.get_pixel(0, 0), img.get_pixel(2, 0));
+        assert_eq!(result.get_pixel(2, 1), img.get_pixel(0, 1));
+        assert_eq!(result.get_pixel(1, 1), img.get_pixel(1, 1));
+    }
+
+    #[test]
+    fn test_flip_vertical() {
+        let img = create_test_image();
+        let result = flip_vertical(&img);
+
+        assert_eq!(result.dimensions(), (3, 2));
+        assert_eq!(result.get_pixel(0, 0), img.get_pixel(0, 1));
+        assert_eq!(result.get_pixel(2, 1), img.get_pixel(2, 0));
+    }
+
+    #[test]
+    fn test_double_flip_is_identity() {
+        let img = create_test_image();
+
+        assert_eq!(flip_horizontal(&flip_horizontal(&img)), img);
+        assert_eq!(flip_vertical(&flip_vertical(&img)), img);
+    }
+
+    #[test]
+    fn test_flip_empty_image() {
+        for (width, height) in [(0, 4), (4, 0), (0, 0)] {
+            let img = RgbImage::new(width, height);
+
+            assert_eq!(flip_horizontal(&img), img);
+            assert_eq!(flip_vertical(&img), img);
+        }
+    }
+
+    #[test]
+    fn test_rotate90() {
+        // 2 wide, 3 tall
+        let img = ImageBuffer::from_fn(2, 3, |x, y| Rgb([x as u8, y as u8, 0]));
+        let result = rotate90(&img);
+
+        assert_eq!(result.dimensions(), (3, 2));
+        // Top-left goes to top-right, bottom-right goes to bottom-left
+        assert_eq!(result.get_pixel(2, 0), img.get_pixel(0, 0));
+        assert_eq!(result.get_pixel(0, 0), img.get_pixel(0, 2));
+        assert_eq!(result.get_pixel(2, 1), img.get_pixel(1, 0));
+        assert_eq!(result.get_pixel(0, 1), img.get_pixel(1, 2));
+    }
+
+    #[test]
+    fn test_rotate180() {
+        let img = ImageBuffer::from_fn(2, 3, |x, y| Rgb([x as u8, y as u8, 0]));
+        let result = rotate180(&img);
+
+        assert_eq!(result.dimensions(), (2, 3));
+        assert_eq!(result.get_pixel(0, 0), img.get_pixel(1, 2));
+        assert_eq!(result.get_pixel(1, 2), img.get_pixel(0, 0));
+        assert_eq!(result.get_pixel(1, 0), img.get_pixel(0, 2));
+    }
+
+    #[test]
+    fn test_rotate270() {
+        let img = ImageBuffer::from_fn(2, 3, |x, y| Rgb([x as u8, y as u8, 0]));
+        let result = rotate270(&img);
+
+        assert_eq!(result.dimensions(), (3, 2));
+        // Top-left goes to bottom-left, bottom-right goes to top-right
+        assert_eq!(result.get_pixel(0, 1), img.get_pixel(0, 0));
+        assert_eq!(result.get_pixel(2, 0), img.get_pixel(1, 2));
+        assert_eq!(result.get_pixel(0, 0), img.get_pixel(1, 0));
+        assert_eq!(result.get_pixel(2, 1), img.get_pixel(0, 2));
+    }
+
+    #[test]
+    fn test_rotations_compose() {
+        let img = create_test_image();
+
+        assert_eq!(rotate90(&rotate90(&img)), rotate180(&img));
+        assert_eq!(rotate270(&rotate90(&img)), img);
+    }
+
+    #[test]
+    fn test_downscale_box() {
+        let img = ImageBuffer::from_fn(5, 2, |x, y| Rgb([(x * 10) as u8, (y * 100) as u8, 7]));
+        let result = downscale_box(&img);
+
+        // The fifth column is dropped
+        assert_eq!(result.dimensions(), (2, 1));
+        assert_eq!(result.get_pixel(0, 0), &Rgb([5, 50, 7]));
+        assert_eq!(result.get_pixel(1, 0), &Rgb([25, 50, 7]));
+    }
+
+    #[test]
+    fn test_downscale_box_degenerate_sizes() {
+        for (width, height) in [(0, 4), (4, 0), (0, 0)] {
+            let img = RgbImage::new(width, height);
+            assert_eq!(downscale_box(&img), img);
+        }
+
+        // A single pixel can't be halved
+        let pixel = RgbImage::from_pixel(1, 1, Rgb([1, 2, 3]));
+        assert_eq!(downscale_box(&pixel), pixel);
+
+        // 1x3: the single column stays, the last row is dropped
+        let column = ImageBuffer::from_fn(1, 3, |_, y| Rgb([(y * 10) as u8, 0, 0]));
+        let result = downscale_box(&column);
+        assert_eq!(result.dimensions(), (1, 1));
+        assert_eq!(result.get_pixel(0, 0), &Rgb([5, 0, 0]));
+    }
+
+    #[test]
+    fn test_image_pyramid() {
+        let img = ImageBuffer::from_fn(64, 16, |x, y| Rgb([x as u8, y as u8, 0]));
+        let pyramid = image_pyramid(&img, 10);
+
+        // 64x16 down to 4x1, where the height can't be halved anymore
+        let dimensions: Vec<_> = pyramid.iter().map(|level| level.dimensions()).collect();
+        assert_eq!(dimensions, vec![(64, 16), (32, 8), (16, 4), (8, 2), (4, 1)]);
+        assert_eq!(pyramid[0], img);
+
+        assert_eq!(image_pyramid(&img, 3).len(), 3);
+        assert_eq!(image_pyramid(&img, 1), vec![img.clone()]);
+        assert!(image_pyramid(&img, 0).is_empty());
+    }
+}
@@ -0,0 +1,26 @@
+use eurorust_2025_workshop::dna_matcher::{memchr_search_bytes, memchr_search_bytes_parallel};
+use rayon::ThreadPoolBuilder;
+
+/// The genome is cut into blocks based on the number of threads: whatever the
+/// pool size, the parallel search must return the sequential result
+#[test]
+fn test_parallel_matcher_across_thread_counts() {
+    let genome = std::fs::read("genome.fasta").expect(
+        "Failed to read genome.fasta\n\n Make sure to run 'cargo run --release --bin generate_fasta'",
+    );
+    let pattern = b"AGTCCGTA";
+
+    let expected = memchr_search_bytes(&genome, pattern);
+    assert_eq!(expected.len(), 4927, "Expected 4927 matching lines");
+
+    for threads in [1, 2, 4, 8] {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let matches = pool.install(|| memchr_search_bytes_parallel(&genome, pattern));
+
+        assert_eq!(matches.len(), 4927, "{threads} threads");
+        assert!(matches == expected, "{threads} threads: different lines");
+    }
+}